@@ -0,0 +1,96 @@
+use num::BigInt;
+use rusty_nets::matrix::Matrix;
+use std::time::{Duration, Instant};
+
+// Plain timing harness, run with `cargo bench`. BigInt elements make every
+// clone a heap allocation, which is where the element-access overhead shows.
+// The multiplication runs go from the original at() loop, through the
+// clone-per-product loop behind `*`, to the borrowing `matmul_ref_into`
+
+const SIZE: usize = 64;
+const RUNS: u32 = 5;
+
+fn bigint_matrix(size: usize, seed: u64) -> Matrix<BigInt> {
+    let data = (0..size * size)
+        .map(|k| BigInt::from((k as u64 * 7919 + seed) % 1_000_003) << 96)
+        .collect();
+
+    Matrix::from(size, size, data)
+}
+
+// The multiplication loop as it stood before `at_ref`: every operand is
+// fetched through `at`, which checks the indices and clones the element
+fn mul_with_at(a: &Matrix<BigInt>, b: &Matrix<BigInt>) -> Matrix<BigInt> {
+    let ((rows, inner), (_, cols)) = (a.shape(), b.shape());
+
+    let mut result = Matrix::new(rows, cols);
+    for i in 0..rows {
+        for j in 0..cols {
+            let mut val = BigInt::from(0);
+            for k in 0..inner {
+                val += a.at(i, k) * b.at(k, j);
+            }
+            result.set(i, j, val);
+        }
+    }
+
+    result
+}
+
+fn time<F: FnMut()>(name: &str, mut func: F) {
+    let mut best = Duration::from_secs(u64::MAX);
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        func();
+        best = best.min(start.elapsed());
+    }
+
+    println!("{:<32} {:>10.3} ms", name, best.as_secs_f64() * 1e3);
+}
+
+fn main() {
+    let a = bigint_matrix(SIZE, 1);
+    let b = bigint_matrix(SIZE, 2);
+
+    time("at, BigInt", || {
+        let mut total = BigInt::from(0);
+        for i in 0..SIZE {
+            for j in 0..SIZE {
+                total += a.at(i, j);
+            }
+        }
+        assert!(total > BigInt::from(0));
+    });
+
+    time("at_ref, BigInt", || {
+        let mut total = BigInt::from(0);
+        for i in 0..SIZE {
+            for j in 0..SIZE {
+                total += a.at_ref(i, j);
+            }
+        }
+        assert!(total > BigInt::from(0));
+    });
+
+    time(&format!("mul {}x{} via at, BigInt", SIZE, SIZE), || {
+        let product = mul_with_at(&a, &b);
+        assert_eq!(product.shape(), (SIZE, SIZE));
+    });
+
+    time(&format!("mul {}x{} cloning, BigInt", SIZE, SIZE), || {
+        let product = &a * &b;
+        assert_eq!(product.shape(), (SIZE, SIZE));
+    });
+
+    time(&format!("mul {}x{} borrowing, BigInt", SIZE, SIZE), || {
+        let mut product = Matrix::new(SIZE, SIZE);
+        a.matmul_ref_into(&b, &mut product);
+        assert_eq!(product.shape(), (SIZE, SIZE));
+    });
+
+    let x = Matrix::from(256, 256, (0..256 * 256).map(|k| k as f64).collect());
+    time("mul 256x256, f64", || {
+        let product = &x * &x;
+        assert_eq!(product.shape(), (256, 256));
+    });
+}
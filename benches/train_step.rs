@@ -0,0 +1,69 @@
+use rusty_nets::layers::{Dense, Layer};
+use rusty_nets::loss::mse_grad;
+use rusty_nets::matrix::{Axis, Matrix};
+use rusty_nets::random::SplitMix64;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Counts heap allocations around one training step of a Dense layer, with
+// the operator-based version of the same arithmetic for comparison. Run
+// with `cargo bench --bench train_step`
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations<F: FnMut()>(mut func: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    func();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    let mut rng = SplitMix64::new(1);
+    let x = Matrix::random_uniform(64, 32, -1., 1., &mut rng);
+    let y = Matrix::random_uniform(64, 16, -1., 1., &mut rng);
+
+    let mut layer = Dense::new(32, 16);
+    let mut step = || {
+        layer.zero_grad();
+        let output = layer.forward(&x);
+        layer.backward(&mse_grad(&output, &y));
+        layer.step(0.01);
+    };
+
+    // The first step fills the pool and the input cache
+    step();
+    let pooled = allocations(&mut step);
+
+    let mut weights = Matrix::random_uniform(32, 16, -1., 1., &mut rng);
+    let mut bias = Matrix::<f64>::new(1, 16);
+    let mut reference = || {
+        let output = (&x * &weights).add_broadcast(&bias);
+        let grad = mse_grad(&output, &y);
+        let grad_weights = &x.transpose() * &grad;
+        let grad_bias = grad.sum_axis(Axis::Col);
+        let _grad_input = &grad * &weights.transpose();
+        weights = &weights - &grad_weights.map(|val| val * 0.01);
+        bias = &bias - &grad_bias.map(|val| val * 0.01);
+    };
+    reference();
+    let unpooled = allocations(&mut reference);
+
+    println!("allocations per step, pooled Dense     {:>4}", pooled);
+    println!("allocations per step, plain operators  {:>4}", unpooled);
+}
@@ -0,0 +1,88 @@
+use crate::matrix::Matrix;
+
+// softmax(Q·K^T / sqrt(d))·V for query rows Q (n x d), key rows K (m x d)
+// and value rows V (m x d_v). The optional n x m mask marks which keys each
+// query may attend to
+
+pub fn scaled_dot_product(
+    q: &Matrix<f64>,
+    k: &Matrix<f64>,
+    v: &Matrix<f64>,
+    mask: Option<&Matrix<bool>>,
+) -> Matrix<f64> {
+    assert_eq!(q.cols, k.cols);
+    assert_eq!(k.rows, v.rows);
+
+    let scale = (q.cols as f64).sqrt();
+    let scores = (q * &k.transpose()).map(|val| val / scale);
+
+    let weights = match mask {
+        Some(mask) => scores.masked_softmax(mask),
+        None => scores.softmax(),
+    };
+
+    &weights * v
+}
+
+// Sinusoidal position encodings, one row per position. Column 2i holds
+// sin(pos / 10000^(2i / d_model)) and column 2i + 1 the matching cosine
+
+pub fn positional_encoding(seq_len: usize, d_model: usize) -> Matrix<f64> {
+    Matrix::from_fn(seq_len, d_model, |pos, j| {
+        let exponent = (j - j % 2) as f64 / d_model as f64;
+        let angle = pos as f64 / 10000f64.powf(exponent);
+
+        if j % 2 == 0 {
+            angle.sin()
+        } else {
+            angle.cos()
+        }
+    })
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::{positional_encoding, scaled_dot_product};
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn scaled_dot_product_test() {
+        let q = Matrix::from(2, 2, vec![1., 0., 0., 0.]);
+        let k = Matrix::from(2, 2, vec![1., 0., 0., 1.]);
+        let v = Matrix::from(2, 2, vec![1., 2., 3., 4.]);
+
+        // Scores [1/sqrt(2), 0] for the first query and [0, 0] for the second
+        let w = 1. / (1. + (-1. / 2f64.sqrt()).exp());
+        let expected = [w + 3. * (1. - w), 2. * w + 4. * (1. - w), 2., 3.];
+
+        let output = scaled_dot_product(&q, &k, &v, None);
+        for (actual, expected) in output.data.iter().zip(expected.iter()) {
+            assert!((actual - expected).abs() < 1e-12);
+        }
+
+        // Causal mask: the first query only sees the first key
+        let mask = Matrix::from(2, 2, vec![true, false, true, true]);
+        let output = scaled_dot_product(&q, &k, &v, Some(&mask));
+        assert_eq!(output, Matrix::from(2, 2, vec![1., 2., 2., 3.]));
+    }
+
+    #[test]
+    fn positional_encoding_test() {
+        let encoding = positional_encoding(4, 6);
+        assert_eq!(encoding.shape(), (4, 6));
+
+        // Position 0 is sin(0) = 0 and cos(0) = 1 throughout
+        for j in 0..6 {
+            assert_eq!(encoding.at(0, j), if j % 2 == 0 { 0. } else { 1. });
+        }
+        assert!((encoding.at(1, 0) - 1f64.sin()).abs() < 1e-12);
+        assert!((encoding.at(2, 3) - (2. / 10000f64.powf(2. / 6.)).cos()).abs() < 1e-12);
+
+        for pos in 1..4 {
+            let differs = (0..6).any(|j| encoding.at(pos, j) != encoding.at(pos - 1, j));
+            assert!(differs);
+        }
+    }
+}
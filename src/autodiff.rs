@@ -0,0 +1,274 @@
+use crate::matrix::{Axis, Matrix};
+
+// Reverse-mode automatic differentiation. Every operation on the tape
+// appends a node holding its value and inputs; `backward` then walks the
+// nodes in reverse, so each node's gradient is complete before it is pushed
+// to its inputs. Vars are plain handles into one tape
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Var(usize);
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Leaf,
+    Add(Var, Var),
+    Sub(Var, Var),
+    MatMul(Var, Var),
+    Hadamard(Var, Var),
+    // Adds a 1 x cols row to every row, like a layer bias
+    AddRow(Var, Var),
+    Sigmoid(Var),
+    Relu(Var),
+    Tanh(Var),
+    Sum(Var),
+    Mse(Var, Var),
+}
+
+#[derive(Debug)]
+struct Node {
+    op: Op,
+    value: Matrix<f64>,
+}
+
+#[derive(Debug, Default)]
+pub struct Tape {
+    nodes: Vec<Node>,
+    grads: Vec<Option<Matrix<f64>>>,
+}
+
+impl Tape {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&mut self, op: Op, value: Matrix<f64>) -> Var {
+        self.nodes.push(Node { op, value });
+        Var(self.nodes.len() - 1)
+    }
+
+    pub fn leaf(&mut self, value: Matrix<f64>) -> Var {
+        self.push(Op::Leaf, value)
+    }
+
+    pub fn value(&self, var: Var) -> &Matrix<f64> {
+        &self.nodes[var.0].value
+    }
+
+    pub fn add(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a) + self.value(b);
+        self.push(Op::Add(a, b), value)
+    }
+
+    pub fn sub(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a) - self.value(b);
+        self.push(Op::Sub(a, b), value)
+    }
+
+    pub fn matmul(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a) * self.value(b);
+        self.push(Op::MatMul(a, b), value)
+    }
+
+    pub fn hadamard(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a).hadamard(self.value(b));
+        self.push(Op::Hadamard(a, b), value)
+    }
+
+    pub fn add_row(&mut self, a: Var, row: Var) -> Var {
+        assert_eq!(self.value(row).rows, 1);
+
+        let value = self.value(a).add_broadcast(self.value(row));
+        self.push(Op::AddRow(a, row), value)
+    }
+
+    pub fn sigmoid(&mut self, a: Var) -> Var {
+        let value = self.value(a).sigmoid();
+        self.push(Op::Sigmoid(a), value)
+    }
+
+    pub fn relu(&mut self, a: Var) -> Var {
+        let value = self.value(a).relu();
+        self.push(Op::Relu(a), value)
+    }
+
+    pub fn tanh(&mut self, a: Var) -> Var {
+        let value = self.value(a).tanh();
+        self.push(Op::Tanh(a), value)
+    }
+
+    pub fn sum(&mut self, a: Var) -> Var {
+        let total = self.value(a).data.iter().sum();
+        self.push(Op::Sum(a), Matrix::from(1, 1, vec![total]))
+    }
+
+    // Mean squared error over every element, as a 1 x 1 value
+    pub fn mse(&mut self, predictions: Var, targets: Var) -> Var {
+        let loss = crate::loss::mse(self.value(predictions), self.value(targets));
+        self.push(
+            Op::Mse(predictions, targets),
+            Matrix::from(1, 1, vec![loss]),
+        )
+    }
+
+    // Gradient of the 1 x 1 `output` with respect to every var on the tape.
+    // Running it again replaces the previous gradients
+    pub fn backward(&mut self, output: Var) {
+        assert_eq!(
+            self.value(output).shape(),
+            (1, 1),
+            "Tape::backward needs a scalar output.."
+        );
+
+        self.grads = (0..self.nodes.len()).map(|_| None).collect();
+        self.grads[output.0] = Some(Matrix::from(1, 1, vec![1.]));
+
+        for index in (0..=output.0).rev() {
+            let grad = match self.grads[index].take() {
+                Some(grad) => grad,
+                None => continue,
+            };
+
+            let value = &self.nodes[index].value;
+            let input = |var: Var| &self.nodes[var.0].value;
+
+            let contributions: Vec<(Var, Matrix<f64>)> = match self.nodes[index].op {
+                Op::Leaf => vec![],
+                Op::Add(a, b) => vec![(a, grad.clone()), (b, grad.clone())],
+                Op::Sub(a, b) => vec![(a, grad.clone()), (b, grad.map(|g| -g))],
+                Op::MatMul(a, b) => vec![
+                    (a, &grad * &input(b).transpose()),
+                    (b, &input(a).transpose() * &grad),
+                ],
+                Op::Hadamard(a, b) => {
+                    vec![(a, grad.hadamard(input(b))), (b, grad.hadamard(input(a)))]
+                }
+                Op::AddRow(a, row) => vec![(a, grad.clone()), (row, grad.sum_axis(Axis::Col))],
+                Op::Sigmoid(a) => vec![(a, grad.map_with_by_ref(value, |g, y| g * y * (1. - y)))],
+                Op::Relu(a) => vec![(
+                    a,
+                    grad.map_with_by_ref(input(a), |g, x| if x > 0. { g } else { 0. }),
+                )],
+                Op::Tanh(a) => vec![(a, grad.map_with_by_ref(value, |g, y| g * (1. - y * y)))],
+                Op::Sum(a) => {
+                    let (rows, cols) = input(a).shape();
+                    vec![(a, Matrix::filled(rows, cols, grad.at(0, 0)))]
+                }
+                Op::Mse(p, t) => {
+                    let upstream = grad.at(0, 0);
+                    let grad_p = crate::loss::mse_grad(input(p), input(t)).map(|g| g * upstream);
+                    let grad_t = grad_p.map(|g| -g);
+                    vec![(p, grad_p), (t, grad_t)]
+                }
+            };
+
+            for (var, contribution) in contributions {
+                self.grads[var.0] = Some(match self.grads[var.0].take() {
+                    Some(total) => &total + &contribution,
+                    None => contribution,
+                });
+            }
+
+            self.grads[index] = Some(grad);
+        }
+    }
+
+    // Zeros if `var` does not feed into the output passed to `backward`
+    pub fn grad(&self, var: Var) -> Matrix<f64> {
+        match self.grads.get(var.0) {
+            Some(Some(grad)) => grad.clone(),
+            _ => {
+                let (rows, cols) = self.value(var).shape();
+                Matrix::new(rows, cols)
+            }
+        }
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::{Tape, Var};
+    use crate::matrix::Matrix;
+    use crate::random::SplitMix64;
+
+    fn mlp_loss(
+        x: &Matrix<f64>,
+        y: &Matrix<f64>,
+        params: &[Matrix<f64>; 4],
+    ) -> (Tape, Vec<Var>, Var) {
+        let mut tape = Tape::new();
+        let x = tape.leaf(x.clone());
+        let y = tape.leaf(y.clone());
+        let vars: Vec<_> = params.iter().map(|p| tape.leaf(p.clone())).collect();
+
+        let hidden = tape.matmul(x, vars[0]);
+        let hidden = tape.add_row(hidden, vars[1]);
+        let hidden = tape.tanh(hidden);
+        let output = tape.matmul(hidden, vars[2]);
+        let output = tape.add_row(output, vars[3]);
+        let output = tape.sigmoid(output);
+        let loss = tape.mse(output, y);
+
+        (tape, vars, loss)
+    }
+
+    #[test]
+    fn mlp_gradient_test() {
+        let mut rng = SplitMix64::new(8);
+        let x = Matrix::random_uniform(5, 3, -1., 1., &mut rng);
+        let y = Matrix::random_uniform(5, 2, 0., 1., &mut rng);
+        let params = [
+            Matrix::random_uniform(3, 4, -1., 1., &mut rng),
+            Matrix::random_uniform(1, 4, -1., 1., &mut rng),
+            Matrix::random_uniform(4, 2, -1., 1., &mut rng),
+            Matrix::random_uniform(1, 2, -1., 1., &mut rng),
+        ];
+
+        let (mut tape, vars, loss) = mlp_loss(&x, &y, &params);
+        tape.backward(loss);
+
+        let h = 1e-6;
+        for (p, var) in vars.iter().enumerate() {
+            let grad = tape.grad(*var);
+            assert_eq!(grad.shape(), params[p].shape());
+
+            for k in 0..params[p].data.len() {
+                let mut plus = params.clone();
+                plus[p].data[k] += h;
+                let mut minus = params.clone();
+                minus[p].data[k] -= h;
+
+                let (tape_plus, _, loss_plus) = mlp_loss(&x, &y, &plus);
+                let (tape_minus, _, loss_minus) = mlp_loss(&x, &y, &minus);
+                let numerical = (tape_plus.value(loss_plus).at(0, 0)
+                    - tape_minus.value(loss_minus).at(0, 0))
+                    / (2. * h);
+
+                assert!((grad.data[k] - numerical).abs() < 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn shared_var_test() {
+        // d/da sum(a·a + a - b) = 2a + 1, and -1 for b
+        let mut tape = Tape::new();
+        let a = tape.leaf(Matrix::from(1, 2, vec![1., -2.]));
+        let b = tape.leaf(Matrix::from(1, 2, vec![5., 5.]));
+        let unused = tape.leaf(Matrix::new(2, 2));
+
+        let square = tape.hadamard(a, a);
+        let sum = tape.add(square, a);
+        let diff = tape.sub(sum, b);
+        let relu = tape.relu(diff);
+        let linear = tape.add(diff, relu);
+        let total = tape.sum(linear);
+        tape.backward(total);
+
+        // relu(diff) is zero everywhere here, so only the linear path counts
+        assert_eq!(tape.grad(a), Matrix::from(1, 2, vec![3., -3.]));
+        assert_eq!(tape.grad(b), Matrix::from(1, 2, vec![-1., -1.]));
+        assert_eq!(tape.grad(unused), Matrix::new(2, 2));
+    }
+}
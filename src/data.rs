@@ -0,0 +1,286 @@
+use crate::matrix::Matrix;
+use crate::random::{self, Rng};
+#[cfg(all(feature = "memmap", unix))]
+use crate::serialize::{self, BinElement};
+#[cfg(all(feature = "memmap", unix))]
+use std::{fs::File, io, path::Path};
+
+// Yields aligned (x, y) mini-batches of rows for one epoch. The final batch
+// holds whatever rows remain, so every row appears exactly once. Build a
+// fresh iterator each epoch; `shuffled` draws a new row order each time
+
+pub struct BatchIterator<'a> {
+    x: &'a Matrix<f64>,
+    y: &'a Matrix<f64>,
+    batch_size: usize,
+    order: Vec<usize>,
+    position: usize,
+}
+
+impl<'a> BatchIterator<'a> {
+    pub fn new(x: &'a Matrix<f64>, y: &'a Matrix<f64>, batch_size: usize) -> Self {
+        assert_eq!(x.rows, y.rows);
+        assert!(batch_size > 0);
+
+        Self {
+            x,
+            y,
+            batch_size,
+            order: (0..x.rows).collect(),
+            position: 0,
+        }
+    }
+
+    pub fn shuffled<R: Rng>(
+        x: &'a Matrix<f64>,
+        y: &'a Matrix<f64>,
+        batch_size: usize,
+        rng: &mut R,
+    ) -> Self {
+        let mut batches = Self::new(x, y, batch_size);
+        random::shuffle(&mut batches.order, rng);
+
+        batches
+    }
+}
+
+impl<'a> Iterator for BatchIterator<'a> {
+    type Item = (Matrix<f64>, Matrix<f64>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.order.len() {
+            return None;
+        }
+
+        let end = (self.position + self.batch_size).min(self.order.len());
+        let indices = &self.order[self.position..end];
+        self.position = end;
+
+        Some((self.x.select_rows(indices), self.y.select_rows(indices)))
+    }
+}
+
+// An f32 matrix written by `write_bin`, read straight from a mapping of the
+// file. Nothing is loaded up front: the OS pages the file in as elements are
+// read, and `to_matrix` copies it out when a regular matrix is needed. The
+// 22-byte header leaves the elements unaligned for f32, so each one is
+// decoded on access rather than handed out as a slice. The file must not be
+// truncated while mapped; reading a page past its new end raises SIGBUS
+#[cfg(all(feature = "memmap", unix))]
+#[derive(Debug)]
+pub struct MappedMatrix {
+    mapping: mmap::Mapping,
+    rows: usize,
+    cols: usize,
+}
+
+#[cfg(all(feature = "memmap", unix))]
+impl MappedMatrix {
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn at(&self, i: usize, j: usize) -> f32 {
+        assert!(i < self.rows);
+        assert!(j < self.cols);
+
+        let start = serialize::HEADER_LEN + (i * self.cols + j) * f32::SIZE;
+        f32::from_le(&self.mapping.as_slice()[start..start + f32::SIZE])
+    }
+
+    pub fn to_matrix(&self) -> Matrix<f32> {
+        let data = self.mapping.as_slice()[serialize::HEADER_LEN..]
+            .chunks(f32::SIZE)
+            .map(f32::from_le)
+            .collect();
+
+        Matrix::from(self.rows, self.cols, data)
+    }
+}
+
+// Maps a file written by `write_bin`. The header and the file length are
+// checked before any element is touched, so a truncated or foreign file is
+// an InvalidData error rather than a read past the mapping
+#[cfg(all(feature = "memmap", unix))]
+pub fn mmap_matrix<P: AsRef<Path>>(path: P) -> io::Result<MappedMatrix> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len() as usize;
+
+    let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+    if file_len < serialize::HEADER_LEN {
+        return Err(invalid("file too short for a matrix header"));
+    }
+
+    let mapping = mmap::Mapping::new(&file, file_len)?;
+
+    let (rows, cols) =
+        serialize::parse_header::<f32>(&mapping.as_slice()[..serialize::HEADER_LEN])?;
+    let expected = rows
+        .checked_mul(cols)
+        .and_then(|len| len.checked_mul(f32::SIZE))
+        .and_then(|len| len.checked_add(serialize::HEADER_LEN));
+    if expected != Some(file_len) {
+        return Err(invalid("file length does not match the matrix header"));
+    }
+
+    Ok(MappedMatrix {
+        mapping,
+        rows,
+        cols,
+    })
+}
+
+#[cfg(all(feature = "memmap", unix))]
+mod mmap {
+    use std::fs::File;
+    use std::io;
+    use std::os::raw::{c_int, c_void};
+    use std::os::unix::io::AsRawFd;
+
+    const PROT_READ: c_int = 1;
+    const MAP_PRIVATE: c_int = 2;
+
+    extern "C" {
+        fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: c_int,
+            flags: c_int,
+            fd: c_int,
+            offset: isize,
+        ) -> *mut c_void;
+        fn munmap(addr: *mut c_void, len: usize) -> c_int;
+    }
+
+    // Read-only private mapping of a whole file, unmapped on drop. `ptr` is
+    // valid for reads of `len` bytes, with `len` non-zero, until then.
+    //
+    // The mapping cannot stop another process truncating the file. Reading
+    // a page past the new end of file raises SIGBUS, so callers must not
+    // map files that may shrink while mapped
+    #[derive(Debug)]
+    pub(super) struct Mapping {
+        ptr: *mut c_void,
+        len: usize,
+    }
+
+    impl Mapping {
+        pub(super) fn new(file: &File, len: usize) -> io::Result<Self> {
+            assert!(len > 0, "Cannot map an empty file..");
+
+            // SAFETY: a null address lets the kernel pick where to place a
+            // fresh mapping, so nothing already mapped is replaced. The fd is
+            // open for the whole call, and a private read-only mapping never
+            // writes back to the file
+            let ptr = unsafe {
+                mmap(
+                    std::ptr::null_mut(),
+                    len,
+                    PROT_READ,
+                    MAP_PRIVATE,
+                    file.as_raw_fd(),
+                    0,
+                )
+            };
+            if ptr as isize == -1 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self { ptr, len })
+        }
+
+        pub(super) fn as_slice(&self) -> &[u8] {
+            // SAFETY: `ptr` came from a successful mmap of `len` bytes and
+            // stays mapped until drop, which the borrow of self outlives.
+            // The pages are readable and nothing in this process writes them
+            unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+        }
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            // SAFETY: `ptr` and `len` are exactly what mmap returned, and no
+            // slice from `as_slice` can outlive the drop
+            unsafe {
+                munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::BatchIterator;
+    use crate::matrix::Matrix;
+    use crate::random::seeded_rng;
+
+    #[test]
+    fn batch_iterator_test() {
+        // Row i of x is [i, 10i] and row i of y is [i]
+        let x = Matrix::from(
+            5,
+            2,
+            (0..5)
+                .flat_map(|i| vec![i as f64, 10. * i as f64])
+                .collect(),
+        );
+        let y = Matrix::from(5, 1, (0..5).map(|i| i as f64).collect());
+
+        let sizes: Vec<_> = BatchIterator::new(&x, &y, 2)
+            .map(|(xb, _)| xb.rows)
+            .collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+
+        let mut seen = Vec::new();
+        for (xb, yb) in BatchIterator::shuffled(&x, &y, 2, &mut seeded_rng(9)) {
+            assert_eq!(xb.rows, yb.rows);
+            for i in 0..xb.rows {
+                assert_eq!(xb.at(i, 0), yb.at(i, 0));
+                assert_eq!(xb.at(i, 1), 10. * yb.at(i, 0));
+                seen.push(yb.at(i, 0) as usize);
+            }
+        }
+
+        seen.sort();
+        assert_eq!(seen, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[cfg(all(feature = "memmap", unix))]
+    #[test]
+    fn mmap_matrix_test() {
+        use super::mmap_matrix;
+        use std::fs;
+        use std::io::ErrorKind;
+
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("rusty-nets-mmap-{}.bin", std::process::id()));
+
+        let mat = Matrix::<f32>::from(2, 3, vec![1.5, -2., 0.25, 1e-20, 3e20, -0.]);
+        let mut buffer = Vec::new();
+        mat.write_bin(&mut buffer).unwrap();
+        fs::write(&path, &buffer).unwrap();
+
+        let mapped = mmap_matrix(&path).unwrap();
+        assert_eq!(mapped.shape(), (2, 3));
+        assert_eq!(mapped.at(1, 1), 3e20);
+        assert_eq!(mapped.to_matrix(), mat);
+        drop(mapped);
+
+        // A header promising more data than the file holds
+        fs::write(&path, &buffer[..buffer.len() - 4]).unwrap();
+        assert_eq!(
+            mmap_matrix(&path).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+
+        fs::write(&path, b"short").unwrap();
+        assert_eq!(
+            mmap_matrix(&path).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
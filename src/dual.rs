@@ -0,0 +1,140 @@
+use num::{Num, One, Zero};
+use std::ops;
+
+// Dual number value + deriv·ε with ε² = 0. Evaluating a function on
+// Dual::variable(x) carries f'(x) along in `deriv`, so a Matrix<Dual>
+// pushed through the ordinary matrix arithmetic yields forward-mode
+// derivatives
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dual {
+    pub value: f64,
+    pub deriv: f64,
+}
+
+impl Dual {
+    pub fn new(value: f64, deriv: f64) -> Self {
+        Self { value, deriv }
+    }
+
+    // The input being differentiated against, with dx/dx = 1
+    pub fn variable(value: f64) -> Self {
+        Self::new(value, 1.)
+    }
+
+    pub fn constant(value: f64) -> Self {
+        Self::new(value, 0.)
+    }
+}
+
+impl ops::Add for Dual {
+    type Output = Dual;
+
+    fn add(self, other: Dual) -> Dual {
+        Dual::new(self.value + other.value, self.deriv + other.deriv)
+    }
+}
+
+impl ops::Sub for Dual {
+    type Output = Dual;
+
+    fn sub(self, other: Dual) -> Dual {
+        Dual::new(self.value - other.value, self.deriv - other.deriv)
+    }
+}
+
+impl ops::Mul for Dual {
+    type Output = Dual;
+
+    fn mul(self, other: Dual) -> Dual {
+        Dual::new(
+            self.value * other.value,
+            self.deriv * other.value + self.value * other.deriv,
+        )
+    }
+}
+
+impl ops::Div for Dual {
+    type Output = Dual;
+
+    fn div(self, other: Dual) -> Dual {
+        Dual::new(
+            self.value / other.value,
+            (self.deriv * other.value - self.value * other.deriv) / other.value.powi(2),
+        )
+    }
+}
+
+// a % b = a - b·trunc(a / b), where the truncation is locally constant
+impl ops::Rem for Dual {
+    type Output = Dual;
+
+    fn rem(self, other: Dual) -> Dual {
+        let quotient = (self.value / other.value).trunc();
+        Dual::new(
+            self.value % other.value,
+            self.deriv - other.deriv * quotient,
+        )
+    }
+}
+
+impl Zero for Dual {
+    fn zero() -> Self {
+        Dual::constant(0.)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.value == 0. && self.deriv == 0.
+    }
+}
+
+impl One for Dual {
+    fn one() -> Self {
+        Dual::constant(1.)
+    }
+}
+
+impl Num for Dual {
+    type FromStrRadixErr = num::traits::ParseFloatError;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        f64::from_str_radix(s, radix).map(Dual::constant)
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::Dual;
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn square_derivative_test() {
+        let x = Matrix::from(1, 1, vec![Dual::variable(3.)]);
+
+        let square = &x * &x;
+        assert_eq!(square.at(0, 0), Dual::new(9., 6.));
+    }
+
+    #[test]
+    fn quadratic_form_test() {
+        // f(t) = v(t)^T·A·v(t) with v(t) = [t, 2t], so f = 14t² and f'(1) = 28
+        let a = Matrix::from(
+            2,
+            2,
+            vec![2., 1., 1., 2.]
+                .into_iter()
+                .map(Dual::constant)
+                .collect(),
+        );
+        let v = Matrix::from(2, 1, vec![Dual::new(1., 1.), Dual::new(2., 2.)]);
+
+        let f = &(&v.transpose() * &a) * &v;
+        assert_eq!(f.at(0, 0), Dual::new(14., 28.));
+
+        let ratio = Dual::variable(2.) / Dual::constant(4.) - Dual::constant(1.);
+        assert_eq!(ratio, Dual::new(-0.5, 0.25));
+        assert_eq!(Dual::variable(7.) % Dual::constant(3.), Dual::new(1., 1.));
+    }
+}
@@ -0,0 +1,54 @@
+use std::{error, fmt};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShapeError {
+    // Shapes of the left and right operands, as (rows, cols)
+    Mismatch {
+        op: &'static str,
+        left: (usize, usize),
+        right: (usize, usize),
+    },
+    // Lengths of two batches that should pair up one to one
+    BatchLength {
+        left: usize,
+        right: usize,
+    },
+    // An operation that needs at least one input was given none
+    Empty {
+        op: &'static str,
+    },
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShapeError::Mismatch { op, left, right } => write!(
+                f,
+                "shape mismatch in {}: {}x{} vs {}x{}",
+                op, left.0, left.1, right.0, right.1
+            ),
+            ShapeError::BatchLength { left, right } => {
+                write!(f, "batch length mismatch: {} vs {}", left, right)
+            }
+            ShapeError::Empty { op } => write!(f, "no matrices given to {}", op),
+        }
+    }
+}
+
+impl error::Error for ShapeError {}
+
+// Position of the first NaN or infinite element found in a matrix
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NonFiniteError {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for NonFiniteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "non-finite value at ({}, {})", self.row, self.col)
+    }
+}
+
+impl error::Error for NonFiniteError {}
@@ -0,0 +1,755 @@
+use crate::error::ShapeError;
+use crate::matrix::{Axis, Matrix};
+use crate::pool::MatrixPool;
+use crate::random::{self, Rng};
+use crate::utils::float;
+use num::Float;
+
+// A differentiable building block of a network. `forward` caches whatever
+// `backward` needs, and parameterized layers accumulate gradients until
+// `step` applies them
+
+pub trait Layer<T = f64> {
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T>;
+
+    // Takes the gradient of the loss with respect to the output and returns
+    // the gradient with respect to the input
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T>;
+
+    fn zero_grad(&mut self) {}
+
+    fn step(&mut self, _learning_rate: T) {}
+}
+
+// Weight initialization schemes. Xavier suits tanh/sigmoid activations,
+// He suits ReLU, and Uniform(a) draws from [-a, a)
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitScheme {
+    Xavier,
+    He,
+    Uniform(f64),
+    Zeros,
+}
+
+// Fully connected layer computing x·W + b for a batch of row samples.
+//
+// Gradients from successive `backward` calls are summed until `zero_grad`,
+// and `step` applies their average. This lets several micro-batches stand
+// in for one large batch
+
+#[derive(Clone, Debug)]
+pub struct Dense<T = f64> {
+    weights: Matrix<T>,
+    bias: Matrix<T>,
+    grad_weights: Matrix<T>,
+    grad_bias: Matrix<T>,
+    accumulated: usize,
+    input: Option<Matrix<T>>,
+    frozen: bool,
+    last_grad_norm: f64,
+    pool: MatrixPool<T>,
+}
+
+impl<T: Float> Dense<T> {
+    pub fn new(in_features: usize, out_features: usize) -> Self {
+        let mut rng = random::default_rng();
+        Self::with_init(in_features, out_features, InitScheme::Xavier, &mut rng)
+    }
+
+    pub fn with_init<R: Rng>(
+        in_features: usize,
+        out_features: usize,
+        init: InitScheme,
+        rng: &mut R,
+    ) -> Self {
+        let limit = match init {
+            InitScheme::Xavier => (6. / (in_features + out_features) as f64).sqrt(),
+            InitScheme::He => (6. / in_features as f64).sqrt(),
+            InitScheme::Uniform(limit) => limit,
+            InitScheme::Zeros => 0.,
+        };
+
+        let data = (0..in_features * out_features)
+            .map(|_| match init {
+                InitScheme::Zeros => T::zero(),
+                _ => float(rng.gen_range(-limit, limit)),
+            })
+            .collect();
+
+        Self::from_weights(Matrix::from(in_features, out_features, data))
+    }
+
+    fn from_weights(weights: Matrix<T>) -> Self {
+        let (in_features, out_features) = weights.shape();
+
+        Self {
+            weights,
+            bias: Matrix::new(1, out_features),
+            grad_weights: Matrix::new(in_features, out_features),
+            grad_bias: Matrix::new(1, out_features),
+            accumulated: 0,
+            input: None,
+            frozen: false,
+            last_grad_norm: 0.,
+            pool: MatrixPool::new(),
+        }
+    }
+
+    pub fn weights(&self) -> &Matrix<T> {
+        &self.weights
+    }
+
+    pub fn weights_mut(&mut self) -> &mut Matrix<T> {
+        &mut self.weights
+    }
+
+    pub fn bias(&self) -> &Matrix<T> {
+        &self.bias
+    }
+
+    // Bias gradient accumulated since the last `zero_grad`, a 1 x out row
+    pub fn grad_bias(&self) -> &Matrix<T> {
+        &self.grad_bias
+    }
+
+    // Replaces the weights, which must keep the in x out shape
+    pub fn set_weights(&mut self, weights: Matrix<T>) -> Result<(), ShapeError> {
+        if weights.shape() != self.weights.shape() {
+            return Err(ShapeError::Mismatch {
+                op: "set_weights",
+                left: self.weights.shape(),
+                right: weights.shape(),
+            });
+        }
+
+        self.weights = weights;
+        Ok(())
+    }
+
+    // A frozen layer still passes gradients back to earlier layers, but
+    // keeps its own parameters fixed
+    pub fn set_trainable(&mut self, trainable: bool) {
+        self.frozen = !trainable;
+    }
+
+    pub fn is_trainable(&self) -> bool {
+        !self.frozen
+    }
+
+    // Frobenius norm of the weight and bias gradients from the most recent
+    // backward pass, before accumulation. Frozen layers leave it untouched
+    pub fn last_grad_norm(&self) -> f64 {
+        self.last_grad_norm
+    }
+}
+
+impl<T: Float> Layer<T> for Dense<T> {
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let mut output = Matrix::new(input.rows, self.weights.cols);
+        input.matmul_into(&self.weights, &mut output);
+        for row in output.data.chunks_mut(self.weights.cols.max(1)) {
+            for (val, &b) in row.iter_mut().zip(self.bias.data.iter()) {
+                *val = *val + b;
+            }
+        }
+
+        // Refill the cached input in place when the batch shape repeats
+        match self.input {
+            Some(ref mut cached) if cached.shape() == input.shape() => {
+                cached.data.copy_from_slice(&input.data)
+            }
+            _ => self.input = Some(input.clone()),
+        }
+
+        output
+    }
+
+    // Adds this batch's gradients to the running totals and returns the
+    // gradient with respect to the input. Temporaries come from the layer's
+    // pool and go back to it, so repeated steps reuse the same buffers
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let input = self
+            .input
+            .as_ref()
+            .expect("Dense::backward called before forward..");
+        let (in_features, out_features) = self.weights.shape();
+
+        if !self.frozen {
+            let mut input_t = self.pool.take(in_features, input.rows);
+            input.transpose_into(&mut input_t);
+
+            let mut grad_weights = self.pool.take(in_features, out_features);
+            input_t.matmul_into(grad_output, &mut grad_weights);
+            // Every sample in the batch adds to the same bias, so its
+            // gradient is the column sum of grad_output
+            let grad_bias = grad_output.sum_axis(Axis::Col);
+
+            self.last_grad_norm = (grad_weights.norm().powi(2) + grad_bias.norm().powi(2)).sqrt();
+
+            accumulate(&mut self.grad_weights, &grad_weights);
+            accumulate(&mut self.grad_bias, &grad_bias);
+            self.accumulated += 1;
+
+            self.pool.recycle(input_t);
+            self.pool.recycle(grad_weights);
+        }
+
+        let mut weights_t = self.pool.take(out_features, in_features);
+        self.weights.transpose_into(&mut weights_t);
+
+        let mut grad_input = Matrix::new(grad_output.rows, in_features);
+        grad_output.matmul_into(&weights_t, &mut grad_input);
+        self.pool.recycle(weights_t);
+
+        grad_input
+    }
+
+    fn zero_grad(&mut self) {
+        self.grad_weights.fill(T::zero());
+        self.grad_bias.fill(T::zero());
+        self.accumulated = 0;
+    }
+
+    // Plain gradient descent on the averaged accumulated gradients
+    fn step(&mut self, learning_rate: T) {
+        if self.frozen || self.accumulated == 0 {
+            return;
+        }
+
+        let scale = learning_rate / float(self.accumulated as f64);
+
+        descend(&mut self.weights, &self.grad_weights, scale);
+        descend(&mut self.bias, &self.grad_bias, scale);
+    }
+}
+
+fn accumulate<T: Float>(total: &mut Matrix<T>, grad: &Matrix<T>) {
+    for (acc, &g) in total.data.iter_mut().zip(grad.data.iter()) {
+        *acc = *acc + g;
+    }
+}
+
+fn descend<T: Float>(param: &mut Matrix<T>, grad: &Matrix<T>, scale: T) {
+    for (val, &g) in param.data.iter_mut().zip(grad.data.iter()) {
+        *val = *val - g * scale;
+    }
+}
+
+// Layer normalization: each row is shifted and scaled to zero mean and unit
+// variance across its features, then passed through a learnable per-feature
+// gain and bias. Unlike batch norm, rows never interact, so it behaves the
+// same for any batch size
+
+#[derive(Clone, Debug)]
+pub struct LayerNorm<T = f64> {
+    gain: Matrix<T>,
+    bias: Matrix<T>,
+    grad_gain: Matrix<T>,
+    grad_bias: Matrix<T>,
+    accumulated: usize,
+    epsilon: T,
+    // Normalized input and 1 / sqrt(var + epsilon) per row, from forward
+    normalized: Option<Matrix<T>>,
+    inv_std: Vec<T>,
+}
+
+impl<T: Float> LayerNorm<T> {
+    pub fn new(features: usize) -> Self {
+        Self {
+            gain: Matrix::filled(1, features, T::one()),
+            bias: Matrix::new(1, features),
+            grad_gain: Matrix::new(1, features),
+            grad_bias: Matrix::new(1, features),
+            accumulated: 0,
+            epsilon: float(1e-5),
+            normalized: None,
+            inv_std: Vec::new(),
+        }
+    }
+
+    pub fn gain(&self) -> &Matrix<T> {
+        &self.gain
+    }
+
+    pub fn bias(&self) -> &Matrix<T> {
+        &self.bias
+    }
+}
+
+impl<T: Float> Layer<T> for LayerNorm<T> {
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        assert_eq!(input.cols, self.gain.cols);
+
+        let mean = input.mean_axis(Axis::Row);
+        let var = input.var_axis(Axis::Row, 0);
+        self.inv_std = var
+            .data
+            .iter()
+            .map(|&v| T::one() / (float::<T>(v) + self.epsilon).sqrt())
+            .collect();
+
+        let mut normalized = input.clone();
+        for (i, row) in normalized.data.chunks_mut(input.cols.max(1)).enumerate() {
+            let mean: T = float(mean.at(i, 0));
+            for val in row.iter_mut() {
+                *val = (*val - mean) * self.inv_std[i];
+            }
+        }
+
+        let output = normalized
+            .mul_broadcast(&self.gain)
+            .add_broadcast(&self.bias);
+        self.normalized = Some(normalized);
+
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let normalized = self
+            .normalized
+            .as_ref()
+            .expect("LayerNorm::backward called before forward..");
+
+        self.grad_gain = &self.grad_gain + &grad_output.hadamard(normalized).sum_axis(Axis::Col);
+        self.grad_bias = &self.grad_bias + &grad_output.sum_axis(Axis::Col);
+        self.accumulated += 1;
+
+        // With g the gradient at the normalized values and n features,
+        // dx = inv_std / n · (n·g - sum(g) - x̂·sum(g·x̂)) for every row
+        let grad_normalized = grad_output.mul_broadcast(&self.gain);
+        let n: T = float(normalized.cols as f64);
+
+        let mut grad_input = grad_normalized.clone();
+        for i in 0..normalized.rows {
+            let (mut sum, mut dot) = (T::zero(), T::zero());
+            for j in 0..normalized.cols {
+                sum = sum + grad_normalized.at(i, j);
+                dot = dot + grad_normalized.at(i, j) * normalized.at(i, j);
+            }
+
+            for j in 0..normalized.cols {
+                let val = n * grad_normalized.at(i, j) - sum - normalized.at(i, j) * dot;
+                grad_input.set(i, j, val * self.inv_std[i] / n);
+            }
+        }
+
+        grad_input
+    }
+
+    fn zero_grad(&mut self) {
+        self.grad_gain.fill(T::zero());
+        self.grad_bias.fill(T::zero());
+        self.accumulated = 0;
+    }
+
+    fn step(&mut self, learning_rate: T) {
+        if self.accumulated == 0 {
+            return;
+        }
+
+        let scale = learning_rate / float(self.accumulated as f64);
+
+        descend(&mut self.gain, &self.grad_gain, scale);
+        descend(&mut self.bias, &self.grad_bias, scale);
+    }
+}
+
+// Element-wise activations. Each caches what its derivative needs from the
+// forward pass: ReLU the pre-activation, sigmoid and tanh their output
+
+#[derive(Clone, Debug, Default)]
+pub struct Relu<T = f64> {
+    input: Option<Matrix<T>>,
+}
+
+impl<T: Float> Relu<T> {
+    pub fn new() -> Self {
+        Self { input: None }
+    }
+}
+
+impl<T: Float> Layer<T> for Relu<T> {
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        self.input = Some(input.clone());
+        input.relu()
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let input = self
+            .input
+            .as_ref()
+            .expect("Relu::backward called before forward..");
+
+        grad_output.map_with_by_ref(input, |g, x| if x > T::zero() { g } else { T::zero() })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Sigmoid<T = f64> {
+    output: Option<Matrix<T>>,
+}
+
+impl<T: Float> Sigmoid<T> {
+    pub fn new() -> Self {
+        Self { output: None }
+    }
+}
+
+impl<T: Float> Layer<T> for Sigmoid<T> {
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let output = input.sigmoid();
+        self.output = Some(output.clone());
+
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let output = self
+            .output
+            .as_ref()
+            .expect("Sigmoid::backward called before forward..");
+
+        grad_output.map_with_by_ref(output, |g, y| g * y * (T::one() - y))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Tanh<T = f64> {
+    output: Option<Matrix<T>>,
+}
+
+impl<T: Float> Tanh<T> {
+    pub fn new() -> Self {
+        Self { output: None }
+    }
+}
+
+impl<T: Float> Layer<T> for Tanh<T> {
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let output = input.tanh();
+        self.output = Some(output.clone());
+
+        output
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let output = self
+            .output
+            .as_ref()
+            .expect("Tanh::backward called before forward..");
+
+        grad_output.map_with_by_ref(output, |g, y| g * (T::one() - y * y))
+    }
+}
+
+// Skip connection around a shape-preserving layer, computing
+// x + inner(x). The gradient reaches the input both through the inner
+// layer and directly along the skip path
+
+#[derive(Clone, Debug)]
+pub struct Residual<L> {
+    inner: L,
+}
+
+impl<L> Residual<L> {
+    pub fn new(inner: L) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+}
+
+impl<T: Float, L: Layer<T>> Layer<T> for Residual<L> {
+    fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let output = self.inner.forward(input);
+        assert!(
+            output.shape() == input.shape(),
+            "{}",
+            ShapeError::Mismatch {
+                op: "residual",
+                left: input.shape(),
+                right: output.shape(),
+            }
+        );
+
+        &output + input
+    }
+
+    fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        &self.inner.backward(grad_output) + grad_output
+    }
+
+    fn zero_grad(&mut self) {
+        self.inner.zero_grad();
+    }
+
+    fn step(&mut self, learning_rate: T) {
+        self.inner.step(learning_rate);
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::{Dense, InitScheme, Layer, LayerNorm, Relu, Residual, Sigmoid, Tanh};
+    use crate::error::ShapeError;
+    use crate::loss::{mse, mse_grad};
+    use crate::matrix::{Axis, Matrix};
+    use crate::random::SplitMix64;
+
+    #[test]
+    fn init_scheme_test() {
+        let mut rng = SplitMix64::new(7);
+
+        let zeros = Dense::<f64>::with_init(3, 4, InitScheme::Zeros, &mut rng);
+        assert_eq!(zeros.weights, Matrix::new(3, 4));
+
+        let uniform = Dense::<f64>::with_init(3, 4, InitScheme::Uniform(0.1), &mut rng);
+        assert!(uniform.weights.data.iter().all(|val| val.abs() <= 0.1));
+
+        let he = Dense::<f64>::with_init(3, 4, InitScheme::He, &mut rng);
+        let limit = (6f64 / 3.).sqrt();
+        assert!(he.weights.data.iter().all(|val| val.abs() <= limit));
+        assert!(he.weights.data.iter().any(|val| *val != 0.));
+    }
+
+    #[test]
+    fn weights_access_test() {
+        let mut layer = Dense::new(2, 3);
+        assert_eq!(layer.weights().shape(), (2, 3));
+        assert_eq!(layer.bias(), &Matrix::new(1, 3));
+
+        let weights = Matrix::from(2, 3, vec![1., 2., 3., 4., 5., 6.]);
+        layer.set_weights(weights.clone()).unwrap();
+        assert_eq!(layer.weights(), &weights);
+
+        layer.weights_mut().set(0, 0, -1.);
+        assert_eq!(layer.weights().at(0, 0), -1.);
+
+        let err = layer.set_weights(Matrix::new(3, 2)).unwrap_err();
+        assert_eq!(
+            err,
+            ShapeError::Mismatch {
+                op: "set_weights",
+                left: (2, 3),
+                right: (3, 2),
+            }
+        );
+        assert_eq!(layer.weights().at(0, 0), -1.);
+    }
+
+    #[test]
+    fn grad_norm_test() {
+        let mut layer = Dense::new(2, 2);
+        assert_eq!(layer.last_grad_norm(), 0.);
+
+        let x = Matrix::from(2, 2, vec![1., 2., -1., 0.5]);
+        let y = Matrix::from(2, 2, vec![1., -1., 0., 2.]);
+
+        let output = layer.forward(&x);
+        let grad_output = mse_grad(&output, &y);
+        layer.backward(&grad_output);
+
+        let expected = (layer.grad_weights.norm().powi(2) + layer.grad_bias.norm().powi(2)).sqrt();
+        assert!(layer.last_grad_norm().is_finite());
+        assert!(layer.last_grad_norm() > 0.);
+        assert!((layer.last_grad_norm() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gradient_accumulation_test() {
+        let x = Matrix::from(2, 2, vec![1., 2., -1., 0.5]);
+        let y = Matrix::from(2, 1, vec![1., -1.]);
+
+        let doubled_x = Matrix::from(4, 2, vec![1., 2., -1., 0.5, 1., 2., -1., 0.5]);
+        let doubled_y = Matrix::from(4, 1, vec![1., -1., 1., -1.]);
+
+        let mut micro = Dense::<f64>::new(2, 1);
+        let mut full = micro.clone();
+
+        for _ in 0..2 {
+            let output = micro.forward(&x);
+            micro.backward(&mse_grad(&output, &y));
+        }
+        micro.step(0.1);
+
+        let output = full.forward(&doubled_x);
+        full.backward(&mse_grad(&output, &doubled_y));
+        full.step(0.1);
+
+        for (a, b) in micro.weights.data.iter().zip(full.weights.data.iter()) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        for (a, b) in micro.bias.data.iter().zip(full.bias.data.iter()) {
+            assert!((a - b).abs() < 1e-12);
+        }
+
+        // Clearing the gradients turns the next step into a no-op
+        let before = micro.weights.clone();
+        micro.zero_grad();
+        micro.step(0.1);
+        assert_eq!(micro.weights, before);
+    }
+
+    #[test]
+    fn frozen_layer_test() {
+        let x = Matrix::from(2, 2, vec![1., 2., -1., 0.5]);
+        let y = Matrix::from(2, 1, vec![1., -1.]);
+
+        let mut first = Dense::new(2, 3);
+        let mut second = Dense::new(3, 1);
+        second.set_trainable(false);
+        assert!(!second.is_trainable());
+
+        let first_before = first.weights.clone();
+        let second_before = second.weights.clone();
+
+        let hidden = first.forward(&x);
+        let output = second.forward(&hidden);
+
+        let grad_hidden = second.backward(&mse_grad(&output, &y));
+        first.backward(&grad_hidden);
+
+        first.step(0.1);
+        second.step(0.1);
+
+        assert_eq!(second.weights, second_before);
+        assert!(grad_hidden.data.iter().any(|val| *val != 0.));
+        assert!(first.weights != first_before);
+    }
+
+    #[test]
+    fn activation_layers_test() {
+        let x = Matrix::<f64>::from(1, 3, vec![-1., 0.5, 2.]);
+        let ones = Matrix::filled(1, 3, 1.);
+
+        let mut relu = Relu::new();
+        assert_eq!(relu.forward(&x), Matrix::from(1, 3, vec![0., 0.5, 2.]));
+        assert_eq!(relu.backward(&ones), Matrix::from(1, 3, vec![0., 1., 1.]));
+
+        let mut tanh = Tanh::new();
+        tanh.forward(&x);
+        let grad = tanh.backward(&ones);
+        for j in 0..3 {
+            assert!((grad.at(0, j) - (1. - x.at(0, j).tanh().powi(2))).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn numerical_gradient_test() {
+        let mut rng = SplitMix64::new(3);
+        let x = Matrix::random_uniform(4, 2, -1., 1., &mut rng);
+        let y = Matrix::random_uniform(4, 1, -1., 1., &mut rng);
+
+        let mut first = Dense::with_init(2, 3, InitScheme::Xavier, &mut rng);
+        let mut activation = Sigmoid::new();
+        let mut second = Dense::with_init(3, 1, InitScheme::Xavier, &mut rng);
+
+        // A single forward and backward pass, relying on the cached values
+        let output = second.forward(&activation.forward(&first.forward(&x)));
+        let grad = second.backward(&mse_grad(&output, &y));
+        first.backward(&activation.backward(&grad));
+
+        let loss = |first: &Dense| {
+            let mut first = first.clone();
+            let mut activation = Sigmoid::new();
+            let mut second = second.clone();
+            mse(&second.forward(&activation.forward(&first.forward(&x))), &y)
+        };
+
+        let h = 1e-6;
+        for i in 0..2 {
+            for j in 0..3 {
+                let mut plus = first.clone();
+                plus.weights.set(i, j, plus.weights.at(i, j) + h);
+                let mut minus = first.clone();
+                minus.weights.set(i, j, minus.weights.at(i, j) - h);
+
+                let numerical = (loss(&plus) - loss(&minus)) / (2. * h);
+                assert!((first.grad_weights.at(i, j) - numerical).abs() < 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn layer_norm_test() {
+        let x = Matrix::from(2, 4, vec![1., 2., 3., 4., -10., 0., 10., 40.]);
+
+        let mut norm = LayerNorm::new(4);
+        let output = norm.forward(&x);
+
+        // Unit gain and zero bias leave the normalized rows as they are
+        for i in 0..2 {
+            let mean: f64 = (0..4).map(|j| output.at(i, j)).sum::<f64>() / 4.;
+            let var: f64 = (0..4).map(|j| output.at(i, j).powi(2)).sum::<f64>() / 4.;
+            assert!(mean.abs() < 1e-12);
+            assert!((var - 1.).abs() < 1e-4);
+        }
+
+        // Gradient with respect to the input against central differences
+        let weights = Matrix::from(2, 4, vec![0.5, -1., 2., 0.1, 1., 1., -0.5, 0.3]);
+        let loss = |x: &Matrix<f64>| LayerNorm::new(4).forward(x).hadamard(&weights).sum();
+
+        let grad = norm.backward(&weights);
+        let h = 1e-6;
+        for i in 0..2 {
+            for j in 0..4 {
+                let mut plus = x.clone();
+                plus.set(i, j, x.at(i, j) + h);
+                let mut minus = x.clone();
+                minus.set(i, j, x.at(i, j) - h);
+
+                let numerical = (loss(&plus) - loss(&minus)) / (2. * h);
+                assert!((grad.at(i, j) - numerical).abs() < 1e-6);
+            }
+        }
+
+        norm.step(0.1);
+        assert!(norm.gain() != &Matrix::filled(1, 4, 1.));
+        assert!(norm.bias() != &Matrix::new(1, 4));
+    }
+
+    #[test]
+    fn residual_test() {
+        let x = Matrix::from(2, 3, vec![1., -2., 0.5, 0., 3., -1.]);
+
+        let mut inner = Dense::with_init(3, 3, InitScheme::Uniform(1.), &mut SplitMix64::new(4));
+        let expected = &x + &inner.forward(&x);
+
+        let mut residual = Residual::new(inner);
+        assert_eq!(residual.forward(&x), expected);
+
+        // The skip path passes the gradient through unchanged
+        let grad_output = Matrix::filled(2, 3, 1.);
+        let inner_grad = residual.inner_mut().backward(&grad_output);
+        assert_eq!(residual.backward(&grad_output), &inner_grad + &grad_output);
+    }
+
+    #[test]
+    fn grad_bias_test() {
+        let mut layer = Dense::with_init(3, 2, InitScheme::Uniform(1.), &mut SplitMix64::new(5));
+
+        let x = Matrix::from(1, 3, vec![0.5, -1., 2.]);
+        let grad_output = Matrix::from(1, 2, vec![0.25, -1.5]);
+        layer.forward(&x);
+        layer.backward(&grad_output);
+        let single = layer.grad_bias().clone();
+        assert_eq!(single.shape(), (1, 2));
+
+        layer.zero_grad();
+        let batch = Matrix::concat(&[x.clone(), x], Axis::Col).unwrap();
+        let batch_grad = Matrix::concat(&[grad_output.clone(), grad_output], Axis::Col).unwrap();
+        layer.forward(&batch);
+        layer.backward(&batch_grad);
+
+        assert_eq!(layer.grad_bias(), &single.map(|val| val * 2.));
+    }
+}
@@ -1,4 +1,23 @@
 extern crate num;
 
+pub mod attention;
+pub mod autodiff;
+pub mod data;
+pub mod dual;
+pub mod error;
+pub mod layers;
+pub mod linalg;
+pub mod loss;
 pub mod matrix;
+pub mod metrics;
+pub mod models;
+pub mod network;
+pub mod pool;
+pub mod quantize;
+pub mod random;
+pub mod serialize;
+pub mod sparse;
+pub mod stats;
+pub mod train;
 pub mod utils;
+pub mod view;
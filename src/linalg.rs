@@ -0,0 +1,618 @@
+use crate::error::ShapeError;
+use crate::matrix::Matrix;
+use crate::utils;
+use num::{traits::Num, ToPrimitive};
+use std::thread;
+
+const JACOBI_MAX_SWEEPS: usize = 100;
+const JACOBI_TOLERANCE: f64 = 1e-24;
+const SYMMETRY_EPSILON: f64 = 1e-9;
+const SVD_RANK_EPSILON: f64 = 1e-12;
+const LSTSQ_TOLERANCE: f64 = 1e-10;
+const PIVOT_EPSILON: f64 = 1e-12;
+
+impl Matrix<f64> {
+    pub fn eig_symmetric(&self) -> Option<(Vec<f64>, Matrix<f64>)> {
+        if !self.is_symmetric(SYMMETRY_EPSILON) {
+            return None;
+        }
+
+        Some(self.jacobi_eigen())
+    }
+
+    // Thin SVD computed from the eigendecomposition of A^T·A. For an m x n
+    // matrix with k = min(m, n), U is m x k and V^T is k x n
+    pub fn svd(&self) -> (Matrix<f64>, Vec<f64>, Matrix<f64>) {
+        let k = self.rows.min(self.cols);
+        let (values, vectors) = (&self.transpose() * self).jacobi_eigen();
+
+        let singular: Vec<f64> = values
+            .iter()
+            .take(k)
+            .map(|val| val.max(0.).sqrt())
+            .collect();
+        let threshold = singular.first().cloned().unwrap_or(0.) * SVD_RANK_EPSILON;
+
+        let mut u = Matrix::<f64>::new(self.rows, k);
+        let mut vt = Matrix::<f64>::new(k, self.cols);
+
+        for (c, &sigma) in singular.iter().enumerate() {
+            for j in 0..self.cols {
+                vt.set(c, j, vectors.at(j, c));
+            }
+
+            if sigma > threshold {
+                // u = A·v / sigma
+                for i in 0..self.rows {
+                    let mut val = 0.;
+                    for j in 0..self.cols {
+                        val += self.at(i, j) * vectors.at(j, c);
+                    }
+                    u.set(i, c, val / sigma);
+                }
+            } else {
+                // No direction is determined by A here, so pick any unit
+                // vector orthogonal to the columns found so far
+                let column = orthonormal_complement(&u, c);
+                for (i, val) in column.into_iter().enumerate() {
+                    u.set(i, c, val);
+                }
+            }
+        }
+
+        (u, singular, vt)
+    }
+
+    // Moore-Penrose pseudo-inverse. Singular values at or below `tolerance`
+    // are treated as zero rather than inverted
+    pub fn pinv(&self, tolerance: f64) -> Matrix<f64> {
+        let (u, singular, vt) = self.svd();
+
+        let mut result = Matrix::<f64>::new(self.cols, self.rows);
+
+        for (c, &sigma) in singular.iter().enumerate() {
+            if sigma <= tolerance {
+                continue;
+            }
+
+            for i in 0..self.cols {
+                for j in 0..self.rows {
+                    let val = result.at(i, j) + vt.at(c, i) * u.at(j, c) / sigma;
+                    result.set(i, j, val);
+                }
+            }
+        }
+
+        result
+    }
+
+    // Minimum-norm solution of A·x ≈ b, one column of x per column of b
+    pub fn lstsq(&self, b: &Matrix<f64>) -> Matrix<f64> {
+        assert_eq!(self.rows, b.rows);
+
+        &self.pinv(LSTSQ_TOLERANCE) * b
+    }
+
+    // Solves A·x = b by Gaussian elimination with partial pivoting, one
+    // column of x per column of b. Returns None if A is singular
+    pub fn solve(&self, b: &Matrix<f64>) -> Option<Matrix<f64>> {
+        assert_eq!(self.rows, self.cols);
+        assert_eq!(self.rows, b.rows);
+
+        let n = self.rows;
+        let mut a = self.clone();
+        let mut x = b.clone();
+
+        for col in 0..n {
+            // A NaN entry is only taken as the pivot when the whole column is NaN
+            let pivot = (col..n)
+                .max_by(|&p, &q| utils::cmp_nan_lowest(&a.at(p, col).abs(), &a.at(q, col).abs()))
+                .unwrap();
+            if a.at(pivot, col).abs() < PIVOT_EPSILON {
+                return None;
+            }
+
+            if pivot != col {
+                for j in 0..n {
+                    let (p, q) = (a.index(col, j), a.index(pivot, j));
+                    a.data.swap(p, q);
+                }
+                for j in 0..x.cols {
+                    let (p, q) = (x.index(col, j), x.index(pivot, j));
+                    x.data.swap(p, q);
+                }
+            }
+
+            for i in col + 1..n {
+                let factor = a.at(i, col) / a.at(col, col);
+                for j in col..n {
+                    a.set(i, j, a.at(i, j) - factor * a.at(col, j));
+                }
+                for j in 0..x.cols {
+                    x.set(i, j, x.at(i, j) - factor * x.at(col, j));
+                }
+            }
+        }
+
+        // Back substitution on the upper triangle
+        for i in (0..n).rev() {
+            for j in 0..x.cols {
+                let mut val = x.at(i, j);
+                for k in i + 1..n {
+                    val -= a.at(i, k) * x.at(k, j);
+                }
+                x.set(i, j, val / a.at(i, i));
+            }
+        }
+
+        Some(x)
+    }
+
+    // Conjugate gradient for symmetric positive-definite systems. Stops once
+    // the residual norm drops below `tol` or after `max_iter` iterations
+    pub fn cg_solve(&self, b: &Matrix<f64>, max_iter: usize, tol: f64) -> Matrix<f64> {
+        assert_eq!(self.rows, self.cols);
+        assert_eq!(self.rows, b.rows);
+        assert_eq!(b.cols, 1);
+
+        let dot = |x: &Matrix<f64>, y: &Matrix<f64>| -> f64 {
+            x.data.iter().zip(y.data.iter()).map(|(a, b)| a * b).sum()
+        };
+
+        let mut x = Matrix::<f64>::new(self.rows, 1);
+        let mut r = b.clone();
+        let mut p = r.clone();
+        let mut rs_old = dot(&r, &r);
+
+        for _ in 0..max_iter {
+            if rs_old.sqrt() < tol {
+                break;
+            }
+
+            let ap = self * &p;
+            let alpha = rs_old / dot(&p, &ap);
+
+            x = x.map_with_by_ref(&p, |xi, pi| xi + alpha * pi);
+            r = r.map_with_by_ref(&ap, |ri, api| ri - alpha * api);
+
+            let rs_new = dot(&r, &r);
+            p = r.map_with_by_ref(&p, |ri, pi| ri + (rs_new / rs_old) * pi);
+            rs_old = rs_new;
+        }
+
+        x
+    }
+
+    // Cyclic Jacobi rotations on a symmetric matrix. Returns the eigenvalues
+    // in descending order, with the matching eigenvectors as columns
+    pub(crate) fn jacobi_eigen(&self) -> (Vec<f64>, Matrix<f64>) {
+        assert_eq!(self.rows, self.cols);
+
+        let n = self.rows;
+        let mut a = self.clone();
+        let mut v = Matrix::<f64>::identity(n);
+
+        let total: f64 = a.data.iter().map(|val| val * val).sum();
+
+        for _ in 0..JACOBI_MAX_SWEEPS {
+            let mut off_diagonal = 0.;
+            for p in 0..n {
+                for q in 0..n {
+                    if p != q {
+                        off_diagonal += a.at(p, q) * a.at(p, q);
+                    }
+                }
+            }
+
+            if off_diagonal <= JACOBI_TOLERANCE * total {
+                break;
+            }
+
+            for p in 0..n {
+                for q in (p + 1)..n {
+                    let apq = a.at(p, q);
+                    if apq == 0. {
+                        continue;
+                    }
+
+                    // Rotation angle chosen so that a[p][q] becomes zero
+                    let theta = (a.at(q, q) - a.at(p, p)) / (2. * apq);
+                    let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+                    let c = 1. / (t * t + 1.).sqrt();
+                    let s = t * c;
+
+                    for k in 0..n {
+                        let (akp, akq) = (a.at(k, p), a.at(k, q));
+                        a.set(k, p, c * akp - s * akq);
+                        a.set(k, q, s * akp + c * akq);
+                    }
+
+                    for k in 0..n {
+                        let (apk, aqk) = (a.at(p, k), a.at(q, k));
+                        a.set(p, k, c * apk - s * aqk);
+                        a.set(q, k, s * apk + c * aqk);
+                    }
+
+                    for k in 0..n {
+                        let (vkp, vkq) = (v.at(k, p), v.at(k, q));
+                        v.set(k, p, c * vkp - s * vkq);
+                        v.set(k, q, s * vkp + c * vkq);
+                    }
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&x, &y| a.at(y, y).total_cmp(&a.at(x, x)));
+
+        let values = order.iter().map(|&k| a.at(k, k)).collect();
+        let mut vectors = Matrix::<f64>::new(n, n);
+        for (col, &k) in order.iter().enumerate() {
+            for row in 0..n {
+                vectors.set(row, col, v.at(row, k));
+            }
+        }
+
+        (values, vectors)
+    }
+}
+
+// Euclidean distance between every row of `a` and every row of `b`, as an
+// a.rows x b.rows matrix. Expands ||a - b||^2 = ||a||^2 - 2a·b + ||b||^2 so
+// the cross terms come from a single product; rounding can push the result
+// slightly below zero, which is clamped before the square root
+
+pub fn pairwise_distances(a: &Matrix<f64>, b: &Matrix<f64>) -> Matrix<f64> {
+    assert_eq!(a.cols, b.cols);
+
+    let a_norms = a.row_norms().map(|norm| norm * norm);
+    let b_norms = b.row_norms().map(|norm| norm * norm);
+
+    let cross = a * &b.transpose();
+
+    let mut result = Matrix::<f64>::new(a.rows, b.rows);
+    for (i, a_norm) in a_norms.data.iter().enumerate() {
+        for (j, b_norm) in b_norms.data.iter().enumerate() {
+            // Clamp the rounding error below zero, but let a NaN through;
+            // f64::max would turn it into a distance of 0
+            let squared = a_norm - 2. * cross.at(i, j) + b_norm;
+            result.set(i, j, if squared < 0. { 0. } else { squared.sqrt() });
+        }
+    }
+
+    result
+}
+
+// Multiplies a[k]·b[k] for every k, spreading the pairs over the available
+// cores. Checks every pair's shapes up front so no work starts on a batch
+// that would fail
+
+pub fn batch_matmul<T>(a: &[Matrix<T>], b: &[Matrix<T>]) -> Result<Vec<Matrix<T>>, ShapeError>
+where
+    T: Clone + Num + Send + Sync,
+{
+    if a.len() != b.len() {
+        return Err(ShapeError::BatchLength {
+            left: a.len(),
+            right: b.len(),
+        });
+    }
+    if let Some((x, y)) = a.iter().zip(b.iter()).find(|(x, y)| x.cols != y.rows) {
+        return Err(ShapeError::Mismatch {
+            op: "batch_matmul",
+            left: x.shape(),
+            right: y.shape(),
+        });
+    }
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = a.len().div_ceil(threads).max(1);
+
+    let products = thread::scope(|scope| {
+        let handles: Vec<_> = a
+            .chunks(chunk)
+            .zip(b.chunks(chunk))
+            .map(|(xs, ys)| {
+                scope.spawn(move || {
+                    xs.iter()
+                        .zip(ys.iter())
+                        .map(|(x, y)| x * y)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    Ok(products)
+}
+
+// Gram-Schmidt a standard basis vector against the first `filled` columns
+fn orthonormal_complement(u: &Matrix<f64>, filled: usize) -> Vec<f64> {
+    for e in 0..u.rows {
+        let mut w = vec![0.; u.rows];
+        w[e] = 1.;
+
+        for c in 0..filled {
+            let dot = u.at(e, c);
+            for (i, val) in w.iter_mut().enumerate() {
+                *val -= dot * u.at(i, c);
+            }
+        }
+
+        let norm = w.iter().map(|val| val * val).sum::<f64>().sqrt();
+        if norm > 1e-6 {
+            return w.into_iter().map(|val| val / norm).collect();
+        }
+    }
+
+    panic!("No orthogonal direction left to complete the basis..")
+}
+
+impl<T: Clone + Num + ToPrimitive> Matrix<T> {
+    // Checks that A^T·A is the identity to within `epsilon`, i.e. that the
+    // columns are orthonormal
+    pub fn is_orthogonal(&self, epsilon: f64) -> bool {
+        let mat = self.to_f64();
+        let product = &mat.transpose() * &mat;
+
+        for i in 0..product.rows {
+            for j in 0..product.cols {
+                let expected = if i == j { 1. } else { 0. };
+                if (product.at(i, j) - expected).abs() > epsilon {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    // Returns the data projected onto the leading principal components, and
+    // the components themselves as columns
+    pub fn pca(&self, n_components: usize) -> (Matrix<f64>, Matrix<f64>) {
+        assert!(n_components <= self.cols);
+
+        let (centered, _) = self.center_columns();
+        let (_, vectors) = self.covariance().jacobi_eigen();
+
+        let mut components = Matrix::<f64>::new(self.cols, n_components);
+        for i in 0..self.cols {
+            for j in 0..n_components {
+                components.set(i, j, vectors.at(i, j));
+            }
+        }
+
+        (&centered * &components, components)
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::{batch_matmul, pairwise_distances};
+    use crate::error::ShapeError;
+    use crate::matrix::Matrix;
+    use crate::random::seeded_rng;
+    use crate::sq_matrix;
+
+    #[test]
+    fn eig_symmetric_test() {
+        let mat = sq_matrix![4., 1., 0., 1., 3., 1., 0., 1., 2.];
+        let expected = [3. + 3f64.sqrt(), 3., 3. - 3f64.sqrt()];
+
+        let (values, vectors) = mat.eig_symmetric().unwrap();
+
+        for (k, (value, expected)) in values.iter().zip(expected.iter()).enumerate() {
+            assert!((value - expected).abs() < 1e-9);
+
+            // A·v == λ·v for every eigenpair
+            let mut v = Matrix::<f64>::new(3, 1);
+            for i in 0..3 {
+                v.set(i, 0, vectors.at(i, k));
+            }
+            let av = &mat * &v;
+            for i in 0..3 {
+                assert!((av.at(i, 0) - value * v.at(i, 0)).abs() < 1e-9);
+            }
+        }
+
+        let asym = sq_matrix![1., 2., 3., 4.];
+        assert!(asym.eig_symmetric().is_none());
+    }
+
+    fn reconstruct(u: &Matrix<f64>, singular: &[f64], vt: &Matrix<f64>) -> Matrix<f64> {
+        let mut sigma = Matrix::<f64>::new(singular.len(), singular.len());
+        for (i, val) in singular.iter().enumerate() {
+            sigma.set(i, i, *val);
+        }
+
+        &(u * &sigma) * vt
+    }
+
+    #[test]
+    fn svd_test() {
+        let tall = Matrix::from(3, 2, vec![3., 2., 2., 3., 2., -2.]);
+        let wide = tall.transpose();
+        let rank_one = Matrix::from(2, 3, vec![1., 2., 3., 2., 4., 6.]);
+
+        for mat in [tall, wide, rank_one].iter() {
+            let (u, singular, vt) = mat.svd();
+
+            assert!(singular.iter().all(|&val| val >= 0.));
+            assert!(singular.windows(2).all(|pair| pair[0] >= pair[1]));
+
+            let rebuilt = reconstruct(&u, &singular, &vt);
+            for (a, b) in rebuilt.data.iter().zip(mat.data.iter()) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn pinv_test() {
+        let full_rank = Matrix::from(3, 2, vec![1., 2., 3., 4., 5., 7.]);
+        let rank_deficient = Matrix::from(2, 3, vec![1., 2., 3., 2., 4., 6.]);
+
+        for mat in [full_rank, rank_deficient].iter() {
+            let pinv = mat.pinv(1e-10);
+            assert_eq!((pinv.rows, pinv.cols), (mat.cols, mat.rows));
+
+            let rebuilt = &(mat * &pinv) * mat;
+            for (a, b) in rebuilt.data.iter().zip(mat.data.iter()) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn lstsq_test() {
+        // Noisy samples of y = 2x + 1, fitted with a [x, 1] design matrix
+        let xs = [0., 1., 2., 3., 4., 5.];
+        let noise = [0.05, -0.04, 0.02, -0.03, 0.04, -0.02];
+
+        let mut a = Matrix::<f64>::new(xs.len(), 2);
+        let mut b = Matrix::<f64>::new(xs.len(), 1);
+        for (i, (x, e)) in xs.iter().zip(noise.iter()).enumerate() {
+            a.set(i, 0, *x);
+            a.set(i, 1, 1.);
+            b.set(i, 0, 2. * x + 1. + e);
+        }
+
+        let fit = a.lstsq(&b);
+        assert_eq!((fit.rows, fit.cols), (2, 1));
+        assert!((fit.at(0, 0) - 2.).abs() < 0.05);
+        assert!((fit.at(1, 0) - 1.).abs() < 0.05);
+    }
+
+    #[test]
+    fn cg_solve_test() {
+        let a = sq_matrix![4., 1., 0., 1., 3., 1., 0., 1., 2.];
+        let expected = Matrix::from(3, 1, vec![1., -2., 3.]);
+        let b = &a * &expected;
+
+        // Exact arithmetic converges in at most n iterations
+        let x = a.cg_solve(&b, 10, 1e-12);
+        for (actual, expected) in x.data.iter().zip(expected.data.iter()) {
+            assert!((actual - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn solve_test() {
+        // Zero leading entry forces a row swap
+        let a = sq_matrix![0., 2., 1., 1., 1., 0., 2., 0., 3.];
+        let expected = Matrix::from(3, 2, vec![1., 0., -2., 1., 3., -1.]);
+        let b = &a * &expected;
+
+        let x = a.solve(&b).unwrap();
+        for (actual, expected) in x.data.iter().zip(expected.data.iter()) {
+            assert!((actual - expected).abs() < 1e-12);
+        }
+
+        let singular = sq_matrix![1., 2., 2., 4.];
+        assert!(singular.solve(&Matrix::from(2, 1, vec![1., 2.])).is_none());
+
+        // NaN propagates into the solution instead of panicking the pivot search
+        let nan = sq_matrix![f64::NAN, 1., 1., 2.];
+        let x = nan.solve(&Matrix::from(2, 1, vec![1., 2.])).unwrap();
+        assert!(x.data.iter().any(|val| val.is_nan()));
+    }
+
+    #[test]
+    fn pairwise_distances_test() {
+        let points = Matrix::from(3, 2, vec![0., 0., 3., 4., 1e8, 1.]);
+
+        let distances = pairwise_distances(&points, &points);
+        assert_eq!(distances.shape(), (3, 3));
+        for i in 0..3 {
+            assert_eq!(distances.at(i, i), 0.);
+        }
+        assert!((distances.at(0, 1) - 5.).abs() < 1e-12);
+        assert_eq!(distances.at(0, 1), distances.at(1, 0));
+
+        let origin = Matrix::from(1, 2, vec![0., 0.]);
+        assert_eq!(pairwise_distances(&origin, &points).shape(), (1, 3));
+
+        let nan = Matrix::from(1, 2, vec![f64::NAN, 0.]);
+        assert!(pairwise_distances(&nan, &points)
+            .data
+            .iter()
+            .all(|d| d.is_nan()));
+    }
+
+    #[test]
+    fn batch_matmul_test() {
+        let a = vec![
+            Matrix::from(2, 2, vec![1, 2, 3, 4]),
+            Matrix::from(1, 3, vec![1, 0, -1]),
+            Matrix::<i32>::identity(2),
+        ];
+        let b = vec![
+            Matrix::from(2, 1, vec![1, 1]),
+            Matrix::from(3, 1, vec![4, 5, 6]),
+            Matrix::from(2, 2, vec![7, 8, 9, 10]),
+        ];
+
+        let products = batch_matmul(&a, &b).unwrap();
+        assert_eq!(products.len(), 3);
+        for (k, product) in products.iter().enumerate() {
+            assert_eq!(product, &(&a[k] * &b[k]));
+        }
+        assert_eq!(products[1], Matrix::from(1, 1, vec![-2]));
+
+        assert_eq!(
+            batch_matmul(&a, &b[..2]).unwrap_err(),
+            ShapeError::BatchLength { left: 3, right: 2 }
+        );
+        assert_eq!(
+            batch_matmul(&b, &a).unwrap_err(),
+            ShapeError::Mismatch {
+                op: "batch_matmul",
+                left: (2, 1),
+                right: (2, 2),
+            }
+        );
+        assert!(batch_matmul::<i32>(&[], &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn is_orthogonal_test() {
+        assert!(Matrix::<i32>::identity(3).is_orthogonal(1e-12));
+
+        let angle = 0.3f64;
+        let rotation = sq_matrix![angle.cos(), -angle.sin(), angle.sin(), angle.cos()];
+        assert!(rotation.is_orthogonal(1e-12));
+
+        let random = Matrix::random_uniform(3, 3, -1., 1., &mut seeded_rng(3));
+        assert!(!random.is_orthogonal(1e-6));
+    }
+
+    #[test]
+    fn pca_test() {
+        let data = Matrix::from(5, 2, vec![-2., 0.1, -1., -0.1, 0., 0.05, 1., -0.05, 2., 0.]);
+
+        let (projected, components) = data.pca(1);
+        assert_eq!((projected.rows, projected.cols), (5, 1));
+        assert_eq!((components.rows, components.cols), (2, 1));
+
+        // The first component should lie along the x axis, up to sign
+        assert!((components.at(0, 0).abs() - 1.).abs() < 1e-2);
+        assert!(components.at(1, 0).abs() < 1e-1);
+
+        // Projections preserve the spread along that axis
+        let sign = components.at(0, 0).signum();
+        assert!((projected.at(4, 0) * sign - 2.).abs() < 1e-1);
+
+        // NaN input propagates into the result rather than panicking
+        let mut data = data;
+        data.set(2, 1, f64::NAN);
+        let (projected, _) = data.pca(1);
+        assert!(projected.data.iter().any(|val| val.is_nan()));
+    }
+}
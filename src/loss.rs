@@ -0,0 +1,145 @@
+use crate::matrix::Matrix;
+use crate::utils::{float, sigmoid};
+use num::Float;
+
+// Mean squared error over every element
+
+pub fn mse<T: Float>(predictions: &Matrix<T>, targets: &Matrix<T>) -> T {
+    assert_eq!(predictions.shape(), targets.shape());
+
+    let total = predictions
+        .data
+        .iter()
+        .zip(targets.data.iter())
+        .fold(T::zero(), |acc, (&p, &t)| acc + (p - t) * (p - t));
+
+    total / float(predictions.data.len() as f64)
+}
+
+pub fn mse_grad<T: Float>(predictions: &Matrix<T>, targets: &Matrix<T>) -> Matrix<T> {
+    assert_eq!(predictions.shape(), targets.shape());
+
+    let scale: T = float(2. / predictions.data.len() as f64);
+    predictions.map_with_by_ref(targets, |p, t| scale * (p - t))
+}
+
+// Hinge loss max(0, 1 - y·f(x)) averaged over samples, with labels of ±1
+
+pub fn hinge<T: Float>(scores: &Matrix<T>, labels: &Matrix<T>) -> T {
+    assert_eq!(scores.shape(), labels.shape());
+
+    let total = scores
+        .data
+        .iter()
+        .zip(labels.data.iter())
+        .fold(T::zero(), |acc, (&s, &y)| {
+            acc + (T::one() - y * s).max(T::zero())
+        });
+
+    total / float(scores.rows as f64)
+}
+
+pub fn hinge_grad<T: Float>(scores: &Matrix<T>, labels: &Matrix<T>) -> Matrix<T> {
+    assert_eq!(scores.shape(), labels.shape());
+
+    let n: T = float(scores.rows as f64);
+
+    // Only samples inside the margin contribute a subgradient
+    scores.map_with_by_ref(labels, |s, y| {
+        if T::one() - y * s > T::zero() {
+            -y / n
+        } else {
+            T::zero()
+        }
+    })
+}
+
+// Binary cross-entropy on raw logits, averaged over every element. Uses
+// max(x, 0) - x·y + ln(1 + e^-|x|) so large logits never overflow
+
+pub fn bce_with_logits<T: Float>(logits: &Matrix<T>, targets: &Matrix<T>) -> (T, Matrix<T>) {
+    assert_eq!(logits.shape(), targets.shape());
+
+    let n: T = float(logits.data.len() as f64);
+
+    let total = logits
+        .data
+        .iter()
+        .zip(targets.data.iter())
+        .fold(T::zero(), |acc, (&x, &y)| {
+            acc + x.max(T::zero()) - x * y + (-x.abs()).exp().ln_1p()
+        });
+
+    let grad = logits.map_with_by_ref(targets, |x, y| (sigmoid(x) - y) / n);
+
+    (total / n, grad)
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::{bce_with_logits, hinge, hinge_grad, mse, mse_grad};
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn mse_test() {
+        let predictions = Matrix::from(2, 1, vec![1., 3.]);
+        let targets = Matrix::from(2, 1, vec![0., 1.]);
+
+        assert_eq!(mse(&predictions, &targets), 2.5);
+        assert_eq!(
+            mse_grad(&predictions, &targets),
+            Matrix::from(2, 1, vec![1., 2.])
+        );
+    }
+
+    #[test]
+    fn f32_loss_test() {
+        let predictions = Matrix::<f32>::from(2, 1, vec![1., 3.]);
+        let targets = Matrix::<f32>::from(2, 1, vec![0., 1.]);
+
+        assert_eq!(mse(&predictions, &targets), 2.5f32);
+
+        let (loss, grad) = bce_with_logits(&predictions.map(|x| x * 100.), &targets);
+        assert!(loss.is_finite());
+        assert!(grad.data.iter().all(|val| val.is_finite()));
+    }
+
+    #[test]
+    fn hinge_test() {
+        let labels = Matrix::from(4, 1, vec![1., -1., 1., -1.]);
+
+        // Correct side of the margin everywhere
+        let scores = Matrix::from(4, 1, vec![2., -1.5, 1., -3.]);
+        assert_eq!(hinge(&scores, &labels), 0.);
+        assert_eq!(hinge_grad(&scores, &labels), Matrix::new(4, 1));
+
+        // One sample inside the margin, one misclassified
+        let scores = Matrix::from(4, 1, vec![0.5, 1., 2., -2.]);
+        assert_eq!(hinge(&scores, &labels), (0.5 + 2.) / 4.);
+        assert_eq!(
+            hinge_grad(&scores, &labels),
+            Matrix::from(4, 1, vec![-0.25, 0.25, 0., 0.])
+        );
+    }
+
+    #[test]
+    fn bce_with_logits_test() {
+        let logits = Matrix::<f64>::from(4, 1, vec![0., 1000., -1000., 1000.]);
+        let targets = Matrix::from(4, 1, vec![1., 1., 0., 0.]);
+
+        let (loss, grad) = bce_with_logits(&logits, &targets);
+        assert!(loss.is_finite());
+        assert!(grad.data.iter().all(|val| val.is_finite()));
+
+        // ln(2) from the zero logit, 1000 from the confident wrong answer
+        let expected = (2f64.ln() + 1000.) / 4.;
+        assert!((loss - expected).abs() < 1e-9);
+
+        let expected = [-0.5 / 4., 0., 0., 1. / 4.];
+        for (actual, expected) in grad.data.iter().zip(expected.iter()) {
+            assert!((actual - expected).abs() < 1e-12);
+        }
+    }
+}
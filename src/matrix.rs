@@ -1,28 +1,117 @@
-use num::{traits::Num, zero};
+use crate::error::{NonFiniteError, ShapeError};
+use crate::random::Rng;
+use crate::utils;
+use num::{traits::Num, zero, Float, NumCast, ToPrimitive};
+use std::hash::{Hash, Hasher};
 use std::ops;
 
+// Axis::Row reduces each row to a single value, Axis::Col each column
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Axis {
+    Row,
+    Col,
+}
+
+// How argmax_axis_with picks among equal maxima. Random draws uniformly
+// from the tied positions
+
+pub enum TieBreak<'a> {
+    First,
+    Last,
+    Random(&'a mut dyn Rng),
+}
+
 #[derive(Clone, Debug)]
 pub struct Matrix<T> {
-    rows: usize,
-    cols: usize,
-    data: Vec<T>,
+    pub(crate) rows: usize,
+    pub(crate) cols: usize,
+    pub(crate) data: Vec<T>,
 }
 
-impl<T: Clone + Num> Matrix<T> {
-    pub fn new(rows: usize, cols: usize) -> Self {
-        Self {
-            rows: rows,
-            cols: cols,
-            data: vec![zero(); rows * cols],
+// Accessors that place no numeric requirements on the element type
+
+impl<T: Clone> Matrix<T> {
+    pub fn from(rows: usize, cols: usize, data: Vec<T>) -> Self {
+        Self { rows, cols, data }
+    }
+
+    pub fn filled(rows: usize, cols: usize, val: T) -> Self {
+        Self::from(rows, cols, vec![val; rows * cols])
+    }
+
+    // Builds each element from its (row, col) position
+    pub fn from_fn<F>(rows: usize, cols: usize, func: F) -> Self
+    where
+        F: Fn(usize, usize) -> T,
+    {
+        let data = (0..rows * cols).map(|k| func(k / cols, k % cols)).collect();
+
+        Self::from(rows, cols, data)
+    }
+
+    // Overwrites every element in place, keeping the allocation
+    pub fn fill(&mut self, val: T) {
+        for elem in self.data.iter_mut() {
+            *elem = val.clone();
         }
     }
 
-    pub fn from(rows: usize, cols: usize, data: Vec<T>) -> Self {
-        Self {
-            rows: rows,
-            cols: cols,
-            data: data,
+    // In-place counterpart of `map`, reusing the existing buffer
+    pub fn apply_inplace<F: Fn(T) -> T>(&mut self, func: F) {
+        for elem in self.data.iter_mut() {
+            *elem = func(elem.clone());
+        }
+    }
+
+    // Reduces every element in row-major order
+    pub fn fold<A, F>(&self, init: A, func: F) -> A
+    where
+        F: Fn(A, T) -> A,
+    {
+        self.data.iter().cloned().fold(init, func)
+    }
+
+    // Same logical matrix, laid out column by column
+    pub fn from_col_major(rows: usize, cols: usize, data: Vec<T>) -> Self {
+        assert_eq!(data.len(), rows * cols);
+
+        let mut row_major = Vec::with_capacity(data.len());
+        for i in 0..rows {
+            for j in 0..cols {
+                row_major.push(data[j * rows + i].clone());
+            }
         }
+
+        Self::from(rows, cols, row_major)
+    }
+
+    pub fn to_col_major(&self) -> Vec<T> {
+        let mut result = Vec::with_capacity(self.data.len());
+        for j in 0..self.cols {
+            for i in 0..self.rows {
+                result.push(self.at(i, j));
+            }
+        }
+
+        result
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    // Panics with a message naming `op` and both shapes unless they match
+    pub(crate) fn assert_same_shape(&self, other: &Matrix<T>, op: &'static str) {
+        assert!(
+            self.shape() == other.shape(),
+            "{}",
+            ShapeError::Mismatch {
+                op,
+                left: self.shape(),
+                right: other.shape(),
+            }
+        );
     }
 
     pub fn index(&self, i: usize, j: usize) -> usize {
@@ -30,10 +119,15 @@ impl<T: Clone + Num> Matrix<T> {
     }
 
     pub fn at(&self, i: usize, j: usize) -> T {
+        self.at_ref(i, j).clone()
+    }
+
+    // Borrowing variant of `at`, for element types that are costly to clone
+    pub fn at_ref(&self, i: usize, j: usize) -> &T {
         assert!(i < self.rows);
         assert!(j < self.cols);
 
-        self.data[self.index(i, j)].clone()
+        &self.data[self.index(i, j)]
     }
 
     pub fn set(&mut self, i: usize, j: usize, val: T) {
@@ -44,265 +138,2535 @@ impl<T: Clone + Num> Matrix<T> {
         self.data[index] = val;
     }
 
-    pub fn map<F>(&self, func: F) -> Self
-    where
-        F: Fn(T) -> T,
-    {
-        let mut result = Matrix::<T>::new(self.rows, self.cols);
+    // The rows (Axis::Row) or columns (Axis::Col) as separate vectors
+    fn lanes(&self, axis: Axis) -> Vec<Vec<T>> {
+        match axis {
+            Axis::Row => (0..self.rows)
+                .map(|i| (0..self.cols).map(|j| self.at(i, j)).collect())
+                .collect(),
+            Axis::Col => (0..self.cols)
+                .map(|j| (0..self.rows).map(|i| self.at(i, j)).collect())
+                .collect(),
+        }
+    }
 
-        for i in 0..self.rows {
-            for j in 0..self.cols {
-                let val = func(self.at(i, j));
-                result.set(i, j, val);
+    // One reduced value per lane, shaped like the other axis reductions
+    fn from_lane_values(axis: Axis, values: Vec<T>) -> Self {
+        match axis {
+            Axis::Row => Self::from(values.len(), 1, values),
+            Axis::Col => Self::from(1, values.len(), values),
+        }
+    }
+
+    // Replicates the matrix in a row_reps x col_reps grid of copies
+    pub fn tile(&self, row_reps: usize, col_reps: usize) -> Self {
+        let rows = self.rows * row_reps;
+        let cols = self.cols * col_reps;
+
+        let mut data = Vec::with_capacity(rows * cols);
+        for i in 0..rows {
+            for j in 0..cols {
+                data.push(self.at(i % self.rows, j % self.cols));
             }
         }
 
-        result
+        Self::from(rows, cols, data)
     }
 
-    pub fn map_with_by_ref<F>(&self, other: &Matrix<T>, func: F) -> Matrix<T>
-    where
-        F: Fn(T, T) -> T,
-    {
-        assert_eq!(self.rows, other.rows);
-        assert_eq!(self.cols, other.cols);
-
-        let mut result = Matrix::<T>::new(self.rows, self.cols);
+    // Surrounds the matrix with a constant border of the given widths
+    pub fn pad(&self, top: usize, bottom: usize, left: usize, right: usize, value: T) -> Self {
+        let mut result = Self::filled(top + self.rows + bottom, left + self.cols + right, value);
 
         for i in 0..self.rows {
             for j in 0..self.cols {
-                let val = func(self.at(i, j), other.at(i, j));
-                result.set(i, j, val);
+                result.set(top + i, left + j, self.at(i, j));
             }
         }
 
         result
     }
 
-    pub fn map_with<F>(&self, other: Self, func: F) -> Self
-    where
-        F: Fn(T, T) -> T,
-    {
-        assert_eq!(self.rows, other.rows);
-        assert_eq!(self.cols, other.cols);
+    // Copy of the block spanning the given row and column ranges
+    pub fn submatrix(&self, rows: ops::Range<usize>, cols: ops::Range<usize>) -> Self {
+        assert!(rows.start <= rows.end && rows.end <= self.rows);
+        assert!(cols.start <= cols.end && cols.end <= self.cols);
 
-        self.map_with_by_ref(&other, func)
+        let mut data = Vec::with_capacity(rows.len() * cols.len());
+        for i in rows.clone() {
+            for j in cols.clone() {
+                data.push(self.at(i, j));
+            }
+        }
+
+        Self::from(rows.len(), cols.len(), data)
     }
-}
 
-// Equality comparisons for Matrix
+    // Every win_rows x win_cols window, stepping `stride` in both directions,
+    // in row-major order of the top-left corners. Nothing is yielded if the
+    // window does not fit
+    pub fn windows(
+        &self,
+        win_rows: usize,
+        win_cols: usize,
+        stride: usize,
+    ) -> impl Iterator<Item = Matrix<T>> + '_ {
+        assert!(stride > 0);
+
+        let count = |size: usize, win: usize| {
+            if win == 0 || win > size {
+                0
+            } else {
+                (size - win) / stride + 1
+            }
+        };
+        let (down, across) = (count(self.rows, win_rows), count(self.cols, win_cols));
 
-impl<T: Clone + Num> PartialEq for Matrix<T> {
-    fn eq(&self, other: &Matrix<T>) -> bool {
-        // Directly compare the slices of the two vectors
-        &self.data[..] == &other.data[..]
+        (0..down * across).map(move |k| {
+            let (top, left) = ((k / across) * stride, (k % across) * stride);
+            self.submatrix(top..top + win_rows, left..left + win_cols)
+        })
     }
-}
 
-// Addition implementation for Matrix and &Matrix
-
-impl<T: Clone + Num> ops::Add<Matrix<T>> for Matrix<T> {
-    type Output = Self;
+    // Rows before `index`, and rows from `index` onwards
+    pub fn split_at_row(&self, index: usize) -> (Self, Self) {
+        assert!(index <= self.rows);
 
-    fn add(self, other: Self) -> Self {
-        &self + &other
+        (
+            self.submatrix(0..index, 0..self.cols),
+            self.submatrix(index..self.rows, 0..self.cols),
+        )
     }
-}
 
-impl<'a, 'b, T: Clone + Num> ops::Add<&'b Matrix<T>> for &'a Matrix<T> {
-    type Output = Matrix<T>;
+    // Columns before `index`, and columns from `index` onwards
+    pub fn split_at_col(&self, index: usize) -> (Self, Self) {
+        assert!(index <= self.cols);
 
-    fn add(self, other: &'b Matrix<T>) -> Matrix<T> {
-        self.map_with_by_ref(other, |a, b| a + b)
+        (
+            self.submatrix(0..self.rows, 0..index),
+            self.submatrix(0..self.rows, index..self.cols),
+        )
     }
-}
 
-// Subtraction implementation for Matrix and &Matrix
+    // The listed columns in the given order; indices may repeat
+    pub fn select_cols(&self, indices: &[usize]) -> Self {
+        if let Some(&j) = indices.iter().find(|&&j| j >= self.cols) {
+            panic!("Column {} out of range for {} columns..", j, self.cols);
+        }
 
-impl<T: Clone + Num> ops::Sub<Matrix<T>> for Matrix<T> {
-    type Output = Self;
+        let mut data = Vec::with_capacity(self.rows * indices.len());
+        for i in 0..self.rows {
+            for &j in indices {
+                data.push(self.at(i, j));
+            }
+        }
 
-    fn sub(self, other: Self) -> Self {
-        &self - &other
+        Self::from(self.rows, indices.len(), data)
     }
-}
 
-impl<'a, 'b, T: Clone + Num> ops::Sub<&'b Matrix<T>> for &'a Matrix<T> {
-    type Output = Matrix<T>;
+    // The listed rows in the given order; indices may repeat
+    pub fn select_rows(&self, indices: &[usize]) -> Self {
+        if let Some(&i) = indices.iter().find(|&&i| i >= self.rows) {
+            panic!("Row {} out of range for {} rows..", i, self.rows);
+        }
 
-    fn sub(self, other: &'b Matrix<T>) -> Matrix<T> {
-        self.map_with_by_ref(other, |a, b| a - b)
+        let mut data = Vec::with_capacity(indices.len() * self.cols);
+        for &i in indices {
+            data.extend_from_slice(&self.data[i * self.cols..(i + 1) * self.cols]);
+        }
+
+        Self::from(indices.len(), self.cols, data)
     }
-}
 
-// Matrix multiplication implementation for Matrix and &Matrix
+    // Joins matrices along an axis. As with the reductions, Axis::Row runs
+    // along each row, placing the matrices side by side, while Axis::Col
+    // runs down each column, stacking them top to bottom
+    pub fn concat(matrices: &[Matrix<T>], axis: Axis) -> Result<Self, ShapeError> {
+        let first = matrices.first().ok_or(ShapeError::Empty { op: "concat" })?;
+
+        for mat in matrices.iter().skip(1) {
+            let compatible = match axis {
+                Axis::Row => mat.rows == first.rows,
+                Axis::Col => mat.cols == first.cols,
+            };
+            if !compatible {
+                return Err(ShapeError::Mismatch {
+                    op: "concat",
+                    left: first.shape(),
+                    right: mat.shape(),
+                });
+            }
+        }
 
-impl<T: Clone + Num> ops::Mul<Matrix<T>> for Matrix<T> {
-    type Output = Self;
+        let result = match axis {
+            Axis::Row => {
+                let cols = matrices.iter().map(|mat| mat.cols).sum();
+                let mut data = Vec::with_capacity(first.rows * cols);
+                for i in 0..first.rows {
+                    for mat in matrices {
+                        data.extend_from_slice(&mat.data[i * mat.cols..(i + 1) * mat.cols]);
+                    }
+                }
+                Self::from(first.rows, cols, data)
+            }
+            Axis::Col => {
+                let rows = matrices.iter().map(|mat| mat.rows).sum();
+                let mut data = Vec::with_capacity(rows * first.cols);
+                for mat in matrices {
+                    data.extend_from_slice(&mat.data);
+                }
+                Self::from(rows, first.cols, data)
+            }
+        };
 
-    fn mul(self, other: Self) -> Self {
-        &self * &other
+        Ok(result)
     }
-}
-
-impl<'a, 'b, T: Clone + Num> ops::Mul<&'b Matrix<T>> for &'a Matrix<T> {
-    type Output = Matrix<T>;
 
-    fn mul(self, other: &'b Matrix<T>) -> Matrix<T> {
-        assert_eq!(self.cols, other.rows);
-
-        let mut result = Matrix::<T>::new(self.rows, other.cols);
+    // Mirrors left to right
+    pub fn flip_horizontal(&self) -> Self {
+        let mut result = self.clone();
 
         for i in 0..self.rows {
-            for j in 0..other.cols {
-                let mut val = num::zero();
-                for k in 0..self.cols {
-                    val = val + self.at(i, k) * other.at(k, j);
-                }
-                result.set(i, j, val);
+            for j in 0..self.cols {
+                result.set(i, j, self.at(i, self.cols - 1 - j));
             }
         }
 
         result
     }
-}
-
-// Macros
 
-#[macro_export]
-macro_rules! sq_matrix {
-    ($elem:expr; $n:expr) => {
-        {
-            let size = $n as usize;
-            let data = vec![$elem; size * size];
+    // Mirrors top to bottom
+    pub fn flip_vertical(&self) -> Self {
+        let mut result = self.clone();
 
-            Matrix {
-                rows: size,
-                cols: size,
-                data: data
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                result.set(i, j, self.at(self.rows - 1 - i, j));
             }
         }
-    };
 
-    ( $( $x:expr ),* ) => {
-        {
-            use crate::utils::get_integral_square_root;
+        result
+    }
 
-            let data_vec = vec![$($x),*];
-            let data_len = data_vec.len();
+    // Rotates a quarter turn counter-clockwise
+    pub fn rot90(&self) -> Self {
+        let mut data = Vec::with_capacity(self.data.len());
 
-            // Ensure that number of elements is a perfect square
-            match get_integral_square_root(data_len) {
-                Some(root) => Matrix {
-                    rows: root,
-                    cols: root,
-                    data: data_vec
-                },
-                None => panic!("Number of elements must be a perfect square..")
+        for i in 0..self.cols {
+            for j in 0..self.rows {
+                data.push(self.at(j, self.cols - 1 - i));
             }
         }
-    };
-}
 
-// Tests
+        Self::from(self.cols, self.rows, data)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::Matrix;
+    // Picks each element from `if_true` where the mask is set, and from
+    // `if_false` elsewhere
+    pub fn select(mask: &Matrix<bool>, if_true: &Matrix<T>, if_false: &Matrix<T>) -> Self {
+        if_true.assert_same_shape(if_false, "select");
+        assert!(
+            mask.shape() == if_true.shape(),
+            "{}",
+            ShapeError::Mismatch {
+                op: "select mask",
+                left: mask.shape(),
+                right: if_true.shape(),
+            }
+        );
 
-    #[test]
-    fn init_test() {
-        let m = 10;
-        let n = 20;
-        let mat = Matrix::<i64>::new(m, n);
+        let data = mask
+            .data
+            .iter()
+            .zip(if_true.data.iter().zip(if_false.data.iter()))
+            .map(|(&m, (t, f))| if m { t.clone() } else { f.clone() })
+            .collect();
 
-        for i in 0..m {
-            for j in 0..n {
-                assert_eq!(mat.at(i, j), 0);
-            }
-        }
+        Self::from(mask.rows, mask.cols, data)
     }
 
-    #[test]
-    fn setter_test() {
-        let mut mat = Matrix::<i64>::new(2, 2);
+    // Every element with its (row, col) position, in row-major order
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        let cols = self.cols;
+        self.data
+            .iter()
+            .enumerate()
+            .map(move |(index, val)| (index / cols, index % cols, val))
+    }
 
-        mat.set(0, 0, 4);
-        mat.set(0, 1, 5);
-        mat.set(1, 0, 6);
-        mat.set(1, 1, 7);
+    pub fn map_cols<F>(&self, func: F) -> Self
+    where
+        F: Fn(Vec<T>) -> Vec<T>,
+    {
+        let mut result = self.clone();
 
-        assert_eq!(mat.at(0, 0), 4);
-        assert_eq!(mat.at(0, 1), 5);
-        assert_eq!(mat.at(1, 0), 6);
-        assert_eq!(mat.at(1, 1), 7);
-    }
+        for j in 0..self.cols {
+            let column = (0..self.rows).map(|i| self.at(i, j)).collect();
+            let mapped = func(column);
+            assert_eq!(mapped.len(), self.rows);
 
-    #[test]
-    fn map_test() {
-        let mat = sq_matrix![4, 5, 6, 7];
-        let expected = sq_matrix![8, 10, 12, 14];
+            for (i, val) in mapped.into_iter().enumerate() {
+                result.set(i, j, val);
+            }
+        }
 
-        let new_mat = mat.map(|val| val * 2);
-        assert_eq!(new_mat, expected);
+        result
     }
+}
 
-    #[test]
-    fn map_with_test() {
-        let mat1 = sq_matrix![1, 2, 3, 4];
-        let mat2 = sq_matrix![10, 20, 30, 40];
+impl<T: Clone + Num> Matrix<T> {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            data: vec![zero(); rows * cols],
+        }
+    }
 
-        let expected = sq_matrix![11, 22, 33, 44];
+    pub fn identity(size: usize) -> Self {
+        let mut result = Matrix::<T>::new(size, size);
 
-        let new_mat = mat1.map_with(mat2, |a, b| a + b);
-        assert_eq!(new_mat, expected);
-    }
+        for i in 0..size {
+            result.set(i, i, num::one());
+        }
 
-    #[test]
-    fn addition_test() {
-        let mat1 = sq_matrix![10, 20, 30, 40];
-        let mat2 = sq_matrix![1, 2, 3, 4];
+        result
+    }
 
-        let expected = sq_matrix![11, 22, 33, 44];
+    // Adds `val` to every diagonal entry of a square matrix, as in A + val·I
+    pub fn diag_add(&self, val: T) -> Self {
+        assert!(
+            self.rows == self.cols,
+            "diag_add needs a square matrix, got {}x{}..",
+            self.rows,
+            self.cols
+        );
 
-        // By reference
-        let new_mat = &mat1 + &mat2;
-        assert_eq!(new_mat, expected);
+        let mut result = self.clone();
+        for i in 0..self.rows {
+            let index = result.index(i, i);
+            result.data[index] = result.data[index].clone() + val.clone();
+        }
 
-        // By value
-        let new_mat = mat1 + mat2;
-        assert_eq!(new_mat, expected);
+        result
     }
 
-    #[test]
-    fn subtraction_test() {
-        let mat1 = sq_matrix![11, 22, 33, 44];
-        let mat2 = sq_matrix![1, 2, 3, 4];
-
-        let expected = sq_matrix![10, 20, 30, 40];
+    // Plain left-to-right sum of every element
+    pub fn sum(&self) -> T {
+        self.data.iter().fold(zero(), |acc, val| acc + val.clone())
+    }
 
-        // By reference
-        let new_mat = &mat1 - &mat2;
-        assert_eq!(new_mat, expected);
+    pub fn trace(&self) -> T {
+        assert!(self.rows == self.cols, "trace needs a square matrix..");
 
-        // By value
-        let new_mat = mat1 - mat2;
-        assert_eq!(new_mat, expected);
+        (0..self.rows).fold(zero(), |acc, i| acc + self.at(i, i))
     }
 
-    #[test]
-    fn multiplication_test() {
-        let mat1 = Matrix::from(2, 3, vec![3, 4, 5, 1, 6, 8]);
-        let mat2 = Matrix::from(3, 2, vec![6, 2, 9, 0, 3, 1]);
+    // trace(A·B) as the sum of A[i,k]·B[k,i], without forming the product
+    pub fn trace_product(&self, other: &Matrix<T>) -> T {
+        assert!(
+            self.cols == other.rows && self.rows == other.cols,
+            "{}",
+            ShapeError::Mismatch {
+                op: "trace_product",
+                left: self.shape(),
+                right: other.shape(),
+            }
+        );
 
-        let expected = sq_matrix![69, 11, 84, 10];
+        let mut total = zero();
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                total = total + self.at(i, k) * other.at(k, i);
+            }
+        }
 
-        // By reference
-        let new_mat = &mat1 * &mat2;
-        assert_eq!(new_mat, expected);
+        total
+    }
 
-        // By value
-        let new_mat = mat1 * mat2;
-        assert_eq!(new_mat, expected);
+    pub fn map<F>(&self, func: F) -> Self
+    where
+        F: Fn(T) -> T,
+    {
+        let mut result = Matrix::<T>::new(self.rows, self.cols);
+
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                let val = func(self.at(i, j));
+                result.set(i, j, val);
+            }
+        }
+
+        result
+    }
+
+    pub fn map_with_by_ref<F>(&self, other: &Matrix<T>, func: F) -> Matrix<T>
+    where
+        F: Fn(T, T) -> T,
+    {
+        self.zip_with(other, "map_with", func)
+    }
+
+    fn zip_with<F>(&self, other: &Matrix<T>, op: &'static str, func: F) -> Matrix<T>
+    where
+        F: Fn(T, T) -> T,
+    {
+        self.assert_same_shape(other, op);
+
+        let mut result = Matrix::<T>::new(self.rows, self.cols);
+
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                let val = func(self.at(i, j), other.at(i, j));
+                result.set(i, j, val);
+            }
+        }
+
+        result
+    }
+
+    pub fn map_with<F>(&self, other: Self, func: F) -> Self
+    where
+        F: Fn(T, T) -> T,
+    {
+        self.map_with_by_ref(&other, func)
+    }
+
+    pub fn hadamard(&self, other: &Matrix<T>) -> Matrix<T> {
+        self.zip_with(other, "hadamard", |a, b| a * b)
+    }
+
+    pub fn hadamard_div(&self, other: &Matrix<T>) -> Matrix<T> {
+        self.zip_with(other, "hadamard_div", |a, b| a / b)
+    }
+
+    // Applies `func` against `other` stretched to this matrix's shape.
+    // `other` may be a single row, a single column, or a single element
+    fn broadcast_with<F>(&self, other: &Matrix<T>, func: F) -> Matrix<T>
+    where
+        F: Fn(T, T) -> T,
+    {
+        assert!(
+            (other.rows == self.rows || other.rows == 1)
+                && (other.cols == self.cols || other.cols == 1),
+            "{}",
+            ShapeError::Mismatch {
+                op: "broadcast",
+                left: self.shape(),
+                right: other.shape(),
+            }
+        );
+
+        let mut result = Matrix::<T>::new(self.rows, self.cols);
+
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                let r = if other.rows == 1 { 0 } else { i };
+                let c = if other.cols == 1 { 0 } else { j };
+                result.set(i, j, func(self.at(i, j), other.at(r, c)));
+            }
+        }
+
+        result
+    }
+
+    pub fn add_broadcast(&self, other: &Matrix<T>) -> Matrix<T> {
+        self.broadcast_with(other, |a, b| a + b)
+    }
+
+    pub fn sub_broadcast(&self, other: &Matrix<T>) -> Matrix<T> {
+        self.broadcast_with(other, |a, b| a - b)
+    }
+
+    pub fn mul_broadcast(&self, other: &Matrix<T>) -> Matrix<T> {
+        self.broadcast_with(other, |a, b| a * b)
+    }
+
+    pub fn div_broadcast(&self, other: &Matrix<T>) -> Matrix<T> {
+        self.broadcast_with(other, |a, b| a / b)
+    }
+
+    // Writes self·other into `out`, reusing its storage
+    pub fn matmul_into(&self, other: &Matrix<T>, out: &mut Matrix<T>) {
+        self.assert_matmul_shapes(other, out);
+
+        // Index the backing vectors directly. Num's operators take their
+        // operands by value, so each product still clones both sides;
+        // `matmul_ref_into` avoids that for types that multiply by reference
+        let (n, p) = (self.cols, other.cols);
+        for i in 0..self.rows {
+            for j in 0..p {
+                let mut val = zero();
+                for k in 0..n {
+                    val = val + self.data[i * n + k].clone() * other.data[k * p + j].clone();
+                }
+                out.data[i * p + j] = val;
+            }
+        }
+    }
+
+    fn assert_matmul_shapes(&self, other: &Matrix<T>, out: &Matrix<T>) {
+        assert!(
+            self.cols == other.rows,
+            "{}",
+            ShapeError::Mismatch {
+                op: "mul",
+                left: self.shape(),
+                right: other.shape(),
+            }
+        );
+        assert!(
+            out.shape() == (self.rows, other.cols),
+            "{}",
+            ShapeError::Mismatch {
+                op: "matmul_into output",
+                left: (self.rows, other.cols),
+                right: out.shape(),
+            }
+        );
+    }
+
+    pub fn try_mul(&self, other: &Matrix<T>) -> Result<Matrix<T>, ShapeError> {
+        if self.cols != other.rows {
+            return Err(ShapeError::Mismatch {
+                op: "mul",
+                left: self.shape(),
+                right: other.shape(),
+            });
+        }
+
+        Ok(self * other)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = Matrix::<T>::new(self.cols, self.rows);
+        self.transpose_into(&mut result);
+
+        result
+    }
+
+    // Writes the transpose into an existing cols x rows matrix, reusing its
+    // buffer
+    pub fn transpose_into(&self, out: &mut Matrix<T>) {
+        assert!(
+            out.shape() == (self.cols, self.rows),
+            "{}",
+            ShapeError::Mismatch {
+                op: "transpose_into output",
+                left: (self.cols, self.rows),
+                right: out.shape(),
+            }
+        );
+
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                out.data[j * self.rows + i] = self.at(i, j);
+            }
+        }
+    }
+
+    pub fn sum_axis(&self, axis: Axis) -> Self {
+        let mut result = match axis {
+            Axis::Row => Matrix::<T>::new(self.rows, 1),
+            Axis::Col => Matrix::<T>::new(1, self.cols),
+        };
+
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                let (r, c) = match axis {
+                    Axis::Row => (i, 0),
+                    Axis::Col => (0, j),
+                };
+                // Move the running total out rather than cloning it
+                let index = result.index(r, c);
+                let total = std::mem::replace(&mut result.data[index], zero());
+                result.data[index] = total + self.at(i, j);
+            }
+        }
+
+        result
+    }
+
+    // Adds row k of `source` into row indices[k], so repeated indices sum
+    // their contributions as an embedding gradient needs
+    pub fn scatter_rows(&mut self, indices: &[usize], source: &Matrix<T>) {
+        assert_eq!(indices.len(), source.rows);
+        assert_eq!(self.cols, source.cols);
+
+        for (k, &row) in indices.iter().enumerate() {
+            assert!(row < self.rows);
+
+            for j in 0..self.cols {
+                let val = self.at(row, j) + source.at(k, j);
+                self.set(row, j, val);
+            }
+        }
+    }
+
+    // Running totals along each row (Axis::Row) or down each column
+    pub fn cumsum_axis(&self, axis: Axis) -> Self {
+        let mut result = self.clone();
+
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                let previous = match axis {
+                    Axis::Row if j > 0 => result.at(i, j - 1),
+                    Axis::Col if i > 0 => result.at(i - 1, j),
+                    _ => continue,
+                };
+                result.set(i, j, previous + self.at(i, j));
+            }
+        }
+
+        result
+    }
+}
+
+impl<T: Clone + Num + ToPrimitive> Matrix<T> {
+    pub fn to_f64(&self) -> Matrix<f64> {
+        let data = self
+            .data
+            .iter()
+            .map(|val| {
+                val.to_f64()
+                    .expect("Element cannot be represented as f64..")
+            })
+            .collect();
+
+        Matrix::from(self.rows, self.cols, data)
+    }
+
+    // Frobenius norm
+    pub fn norm(&self) -> f64 {
+        self.data
+            .iter()
+            .map(|val| {
+                val.to_f64()
+                    .expect("Element cannot be represented as f64..")
+                    .powi(2)
+            })
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    // Cheap single-pass health checks for monitoring training, e.g. the
+    // fraction of exact zeros after a ReLU shows how many units are dead.
+    // An empty matrix gives 0, so a NaN always means NaN data
+    pub fn mean_abs(&self) -> f64 {
+        if self.data.is_empty() {
+            return 0.;
+        }
+
+        let total: f64 = self
+            .data
+            .iter()
+            .map(|val| {
+                val.to_f64()
+                    .expect("Element cannot be represented as f64..")
+                    .abs()
+            })
+            .sum();
+
+        total / self.data.len() as f64
+    }
+
+    pub fn frac_zeros(&self) -> f64 {
+        if self.data.is_empty() {
+            return 0.;
+        }
+
+        let zeros = self.data.iter().filter(|val| val.is_zero()).count();
+        zeros as f64 / self.data.len() as f64
+    }
+
+    pub fn frac_nan(&self) -> f64 {
+        if self.data.is_empty() {
+            return 0.;
+        }
+
+        let nans = self
+            .data
+            .iter()
+            .filter(|val| val.to_f64().is_some_and(f64::is_nan))
+            .count();
+        nans as f64 / self.data.len() as f64
+    }
+
+    // Counts per equal-width bin plus the bins+1 edges. Without a range the
+    // observed min and max are used, widened by 0.5 either side when they
+    // coincide. As with the usual convention the last bin includes its
+    // upper edge; NaNs and values outside the range are not counted
+    pub fn histogram(&self, bins: usize, range: Option<(f64, f64)>) -> (Vec<usize>, Vec<f64>) {
+        assert!(bins > 0);
+
+        let values: Vec<f64> = self
+            .data
+            .iter()
+            .map(|val| {
+                val.to_f64()
+                    .expect("Element cannot be represented as f64..")
+            })
+            .filter(|val| !val.is_nan())
+            .collect();
+
+        let (low, high) = match range {
+            Some((low, high)) => {
+                assert!(low < high, "Histogram range must be increasing..");
+                (low, high)
+            }
+            None if values.is_empty() => (0., 1.),
+            None => {
+                let low = values.iter().cloned().fold(f64::INFINITY, f64::min);
+                let high = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                if low == high {
+                    (low - 0.5, high + 0.5)
+                } else {
+                    (low, high)
+                }
+            }
+        };
+
+        let width = (high - low) / bins as f64;
+        let edges = (0..=bins).map(|k| low + k as f64 * width).collect();
+
+        let mut counts = vec![0; bins];
+        for val in values {
+            if val < low || val > high {
+                continue;
+            }
+            let bin = (((val - low) / width) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+
+        (counts, edges)
+    }
+
+    // L2 norm of each row, as a rows x 1 matrix
+    pub fn row_norms(&self) -> Matrix<f64> {
+        let mat = self.to_f64();
+        let norms = (0..mat.rows)
+            .map(|i| {
+                (0..mat.cols)
+                    .map(|j| mat.at(i, j).powi(2))
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .collect();
+
+        Matrix::from(mat.rows, 1, norms)
+    }
+
+    // Scales every row to unit L2 norm. All-zero rows stay zero
+    pub fn normalize_rows(&self) -> Matrix<f64> {
+        let mut mat = self.to_f64();
+        let norms = self.row_norms();
+
+        for i in 0..mat.rows {
+            let norm = norms.at(i, 0);
+            if norm == 0. {
+                continue;
+            }
+            for j in 0..mat.cols {
+                mat.set(i, j, mat.at(i, j) / norm);
+            }
+        }
+
+        mat
+    }
+
+    pub fn is_symmetric(&self, epsilon: f64) -> bool {
+        if self.rows != self.cols {
+            return false;
+        }
+
+        let mat = self.to_f64();
+
+        for i in 0..mat.rows {
+            for j in (i + 1)..mat.cols {
+                if (mat.at(i, j) - mat.at(j, i)).abs() > epsilon {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    pub fn symmetrize(&self) -> Matrix<f64> {
+        assert_eq!(self.rows, self.cols);
+
+        let mat = self.to_f64();
+        (&mat + &mat.transpose()).map(|val| val / 2.)
+    }
+
+    // Every monomial of the features up to `degree`, starting with the bias
+    // column. Columns go by degree, then by feature index, so two features
+    // at degree 2 give [1, a, b, a², ab, b²]
+    pub fn polynomial_features(&self, degree: usize) -> Matrix<f64> {
+        let mat = self.to_f64();
+
+        // Each monomial as a non-decreasing list of feature indices
+        let mut monomials = vec![vec![]];
+        let mut previous: Vec<Vec<usize>> = vec![vec![]];
+        for _ in 0..degree {
+            let mut current = Vec::new();
+            for monomial in previous.iter() {
+                let start = monomial.last().cloned().unwrap_or(0);
+                for j in start..self.cols {
+                    let mut next = monomial.clone();
+                    next.push(j);
+                    current.push(next);
+                }
+            }
+            monomials.extend(current.iter().cloned());
+            previous = current;
+        }
+
+        let mut data = Vec::with_capacity(self.rows * monomials.len());
+        for i in 0..self.rows {
+            for monomial in monomials.iter() {
+                data.push(monomial.iter().map(|&j| mat.at(i, j)).product());
+            }
+        }
+
+        Matrix::from(self.rows, monomials.len(), data)
+    }
+
+    pub fn mean_axis(&self, axis: Axis) -> Matrix<f64> {
+        let count = match axis {
+            Axis::Row => self.cols,
+            Axis::Col => self.rows,
+        };
+
+        self.to_f64().sum_axis(axis).map(|val| val / count as f64)
+    }
+
+    // Divides the squared deviations by n - ddof, so ddof = 0 gives the
+    // population variance and ddof = 1 the sample variance
+    pub fn var_axis(&self, axis: Axis, ddof: usize) -> Matrix<f64> {
+        let values = self
+            .to_f64()
+            .lanes(axis)
+            .into_iter()
+            .map(|lane| {
+                assert!(lane.len() > ddof);
+
+                let mean = lane.iter().sum::<f64>() / lane.len() as f64;
+                let squares: f64 = lane.iter().map(|val| (val - mean) * (val - mean)).sum();
+
+                squares / (lane.len() - ddof) as f64
+            })
+            .collect();
+
+        Matrix::from_lane_values(axis, values)
+    }
+
+    pub fn std_axis(&self, axis: Axis, ddof: usize) -> Matrix<f64> {
+        self.var_axis(axis, ddof).map(f64::sqrt)
+    }
+
+    // q-th quantile of each row or column for q in [0, 1], interpolating
+    // linearly between the two nearest ranks. A lane holding any NaN has a
+    // NaN quantile
+    pub fn percentile_axis(&self, axis: Axis, q: f64) -> Matrix<f64> {
+        assert!((0. ..=1.).contains(&q));
+
+        let values = self
+            .to_f64()
+            .lanes(axis)
+            .into_iter()
+            .map(|mut lane| {
+                assert!(!lane.is_empty());
+                if lane.iter().any(|val| val.is_nan()) {
+                    return f64::NAN;
+                }
+                lane.sort_by(f64::total_cmp);
+
+                let rank = q * (lane.len() - 1) as f64;
+                let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+                let weight = rank - lower as f64;
+
+                lane[lower] * (1. - weight) + lane[upper] * weight
+            })
+            .collect();
+
+        Matrix::from_lane_values(axis, values)
+    }
+
+    pub fn median_axis(&self, axis: Axis) -> Matrix<f64> {
+        self.percentile_axis(axis, 0.5)
+    }
+
+    pub fn center_columns(&self) -> (Matrix<f64>, Vec<f64>) {
+        let mat = self.to_f64();
+        let means = self.mean_axis(Axis::Col).data;
+
+        let mut result = mat.clone();
+        for i in 0..mat.rows {
+            for (j, mean) in means.iter().enumerate() {
+                result.set(i, j, mat.at(i, j) - mean);
+            }
+        }
+
+        (result, means)
+    }
+
+    pub fn covariance(&self) -> Matrix<f64> {
+        assert!(self.rows > 1);
+
+        let (centered, _) = self.center_columns();
+        let scale = 1. / (self.rows - 1) as f64;
+
+        (&centered.transpose() * &centered).map(|val| val * scale)
+    }
+
+    // A·A^T computed from row dot products, without building A^T
+    pub fn gram(&self) -> Matrix<f64> {
+        let mat = self.to_f64();
+        let mut result = Matrix::<f64>::new(self.rows, self.rows);
+
+        for i in 0..mat.rows {
+            for j in i..mat.rows {
+                let mut val = 0.;
+                for k in 0..mat.cols {
+                    val += mat.at(i, k) * mat.at(j, k);
+                }
+                result.set(i, j, val);
+                result.set(j, i, val);
+            }
+        }
+
+        result
+    }
+
+    pub fn matmul_acc<Acc>(&self, other: &Matrix<T>) -> Matrix<Acc>
+    where
+        Acc: Clone + Num + NumCast,
+    {
+        assert!(
+            self.cols == other.rows,
+            "{}",
+            ShapeError::Mismatch {
+                op: "matmul_acc",
+                left: self.shape(),
+                right: other.shape(),
+            }
+        );
+
+        // Widen each operand before multiplying, so neither the products
+        // nor the running sum can overflow the narrower element type
+        let widen = |val: T| -> Acc {
+            <Acc as NumCast>::from(val).expect("Element does not fit in the accumulator type..")
+        };
+
+        let mut result = Matrix::<Acc>::new(self.rows, other.cols);
+
+        for i in 0..self.rows {
+            for j in 0..other.cols {
+                let mut val = zero();
+                for k in 0..self.cols {
+                    val = val + widen(self.at(i, k)) * widen(other.at(k, j));
+                }
+                result.set(i, j, val);
+            }
+        }
+
+        result
+    }
+}
+
+// Comparisons for ordered element types
+
+impl<T: Clone + Num + PartialOrd> Matrix<T> {
+    // Maximum of each row or column together with its position along that
+    // row or column. Ties resolve to the first index
+    pub fn max_with_index_axis(&self, axis: Axis) -> (Matrix<T>, Vec<usize>) {
+        assert!(self.rows > 0 && self.cols > 0);
+
+        let (outer, inner) = match axis {
+            Axis::Row => (self.rows, self.cols),
+            Axis::Col => (self.cols, self.rows),
+        };
+        let at = |o: usize, k: usize| match axis {
+            Axis::Row => self.at_ref(o, k),
+            Axis::Col => self.at_ref(k, o),
+        };
+
+        let mut values = Vec::with_capacity(outer);
+        let mut indices = Vec::with_capacity(outer);
+
+        for o in 0..outer {
+            let mut best = 0;
+            let mut best_val = at(o, 0);
+            for k in 1..inner {
+                let val = at(o, k);
+                if val > best_val {
+                    best = k;
+                    best_val = val;
+                }
+            }
+            values.push(best_val.clone());
+            indices.push(best);
+        }
+
+        let result = match axis {
+            Axis::Row => Matrix::from(outer, 1, values),
+            Axis::Col => Matrix::from(1, outer, values),
+        };
+
+        (result, indices)
+    }
+
+    pub fn minimum(&self, other: &Matrix<T>) -> Matrix<T> {
+        self.zip_with(other, "minimum", |a, b| if b < a { b } else { a })
+    }
+
+    pub fn maximum(&self, other: &Matrix<T>) -> Matrix<T> {
+        self.zip_with(other, "maximum", |a, b| if b > a { b } else { a })
+    }
+
+    pub fn max_axis(&self, axis: Axis) -> Matrix<T> {
+        self.max_with_index_axis(axis).0
+    }
+
+    pub fn argmax_axis(&self, axis: Axis) -> Vec<usize> {
+        self.max_with_index_axis(axis).1
+    }
+
+    pub fn argmax_axis_with(&self, axis: Axis, mut tie: TieBreak) -> Vec<usize> {
+        let (maxima, first) = self.max_with_index_axis(axis);
+
+        let (outer, inner) = match axis {
+            Axis::Row => (self.rows, self.cols),
+            Axis::Col => (self.cols, self.rows),
+        };
+
+        (0..outer)
+            .map(|o| {
+                let best = maxima.data[o].clone();
+                let tied: Vec<usize> = (0..inner)
+                    .filter(|&k| {
+                        let val = match axis {
+                            Axis::Row => self.at_ref(o, k),
+                            Axis::Col => self.at_ref(k, o),
+                        };
+                        *val == best
+                    })
+                    .collect();
+
+                // A NaN maximum equals nothing, so keep the plain argmax
+                if tied.is_empty() {
+                    return first[o];
+                }
+
+                match tie {
+                    TieBreak::First => tied[0],
+                    TieBreak::Last => tied[tied.len() - 1],
+                    TieBreak::Random(ref mut rng) => tied[rng.gen_index(tied.len())],
+                }
+            })
+            .collect()
+    }
+
+    // Column indices of the k largest entries in each row, largest first.
+    // The sort is stable, so ties keep the lower index first, and NaN
+    // entries rank after every number
+    pub fn topk_rows(&self, k: usize) -> Vec<Vec<usize>> {
+        assert!(k <= self.cols);
+
+        (0..self.rows)
+            .map(|i| {
+                let mut order: Vec<usize> = (0..self.cols).collect();
+                order.sort_by(|&a, &b| utils::cmp_nan_lowest(self.at_ref(i, b), self.at_ref(i, a)));
+                order.truncate(k);
+                order
+            })
+            .collect()
+    }
+}
+
+// Reductions over boolean masks
+
+impl Matrix<bool> {
+    pub fn count_true(&self) -> usize {
+        self.data.iter().filter(|&&val| val).count()
+    }
+
+    pub fn any(&self) -> bool {
+        self.data.iter().any(|&val| val)
+    }
+
+    pub fn all(&self) -> bool {
+        self.data.iter().all(|&val| val)
+    }
+}
+
+// Element-wise math for floating point matrices
+
+impl<T: Clone + Float> Matrix<T> {
+    pub fn powf(&self, p: T) -> Self {
+        self.map(|val| val.powf(p))
+    }
+
+    pub fn exp(&self) -> Self {
+        self.map(|val| val.exp())
+    }
+
+    pub fn ln(&self) -> Self {
+        self.map(|val| val.ln())
+    }
+
+    pub fn sqrt(&self) -> Self {
+        self.map(|val| val.sqrt())
+    }
+
+    pub fn abs(&self) -> Self {
+        self.map(|val| val.abs())
+    }
+
+    // Fails on the first NaN or infinity in row-major order, so a training
+    // loop can stop as soon as an update diverges
+    pub fn assert_finite(&self) -> Result<(), NonFiniteError> {
+        match self.data.iter().position(|val| !val.is_finite()) {
+            Some(index) => Err(NonFiniteError {
+                row: index / self.cols,
+                col: index % self.cols,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // Kahan compensated sum, carrying the low-order bits each addition
+    // would otherwise drop
+    pub fn sum_kahan(&self) -> T {
+        let mut total = T::zero();
+        let mut compensation = T::zero();
+
+        for &val in self.data.iter() {
+            let y = val - compensation;
+            let t = total + y;
+            compensation = (t - total) - y;
+            total = t;
+        }
+
+        total
+    }
+
+    // Element-wise a / (b + epsilon), keeping zero denominators finite
+    pub fn safe_hadamard_div(&self, other: &Matrix<T>, epsilon: T) -> Self {
+        self.zip_with(other, "safe_hadamard_div", |a, b| a / (b + epsilon))
+    }
+
+    // Largest absolute element-wise difference and where it occurs. A NaN
+    // difference is reported straight away as the worst possible mismatch
+    pub fn max_abs_diff(&self, other: &Matrix<T>) -> (T, usize, usize) {
+        self.assert_same_shape(other, "max_abs_diff");
+
+        let mut worst = (T::zero(), 0, 0);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                let diff = (self.at(i, j) - other.at(i, j)).abs();
+                if diff.is_nan() {
+                    return (diff, i, j);
+                }
+                if diff > worst.0 {
+                    worst = (diff, i, j);
+                }
+            }
+        }
+
+        worst
+    }
+
+    pub fn sigmoid(&self) -> Self {
+        self.map(utils::sigmoid)
+    }
+
+    pub fn relu(&self) -> Self {
+        self.map(|val| val.max(T::zero()))
+    }
+
+    pub fn tanh(&self) -> Self {
+        self.map(|val| val.tanh())
+    }
+
+    // log(sum(exp(x))) of each row or column, computed as
+    // max + log(sum(exp(x - max))) so large values cannot overflow. A lane
+    // whose maximum is infinite reduces to that maximum
+    pub fn logsumexp_axis(&self, axis: Axis) -> Self {
+        let mut result = self.max_axis(axis);
+
+        for (max, lane) in result.data.iter_mut().zip(self.lanes(axis)) {
+            if !max.is_finite() {
+                continue;
+            }
+
+            let total = lane
+                .iter()
+                .fold(T::zero(), |acc, &val| acc + (val - *max).exp());
+
+            *max = *max + total.ln();
+        }
+
+        result
+    }
+
+    // Row-wise softmax, shifted by each row's maximum so exp never
+    // overflows. A row that is entirely -inf comes out as all zeros
+    pub fn softmax(&self) -> Self {
+        let mut result = self.clone();
+
+        for row in result.data.chunks_mut(self.cols.max(1)) {
+            let max = row.iter().cloned().fold(T::neg_infinity(), T::max);
+            if max == T::neg_infinity() {
+                row.iter_mut().for_each(|val| *val = T::zero());
+                continue;
+            }
+
+            let mut total = T::zero();
+            for val in row.iter_mut() {
+                *val = (*val - max).exp();
+                total = total + *val;
+            }
+            for val in row.iter_mut() {
+                *val = *val / total;
+            }
+        }
+
+        result
+    }
+
+    // Softmax of logits / temperature. Low temperatures sharpen each row
+    // towards its argmax, high ones flatten it towards uniform
+    pub fn softmax_temperature(&self, temperature: f64) -> Self {
+        assert!(temperature > 0., "Softmax temperature must be positive..");
+
+        let temperature: T = utils::float(temperature);
+        self.map(|val| val / temperature).softmax()
+    }
+
+    // Softmax with `false` positions forced to -inf, so they get exactly
+    // zero probability and the rest of the row renormalizes
+    pub fn masked_softmax(&self, mask: &Matrix<bool>) -> Self {
+        assert!(
+            mask.shape() == self.shape(),
+            "{}",
+            ShapeError::Mismatch {
+                op: "masked_softmax",
+                left: self.shape(),
+                right: mask.shape(),
+            }
+        );
+
+        let blocked = Self::filled(self.rows, self.cols, T::neg_infinity());
+        Self::select(mask, self, &blocked).softmax()
+    }
+}
+
+// Reductions whose result does not depend on how the work is split
+
+const PARALLEL_SUM_THRESHOLD: usize = 1 << 16;
+
+impl<T: Clone + Float + Send + Sync> Matrix<T> {
+    // Pairwise sum over every element. More accurate than `sum`, and large
+    // matrices are split across threads without changing a single bit of
+    // the result
+    pub fn sum_deterministic(&self) -> T {
+        if self.data.len() < PARALLEL_SUM_THRESHOLD {
+            return utils::pairwise_sum(&self.data);
+        }
+
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let depth = (usize::BITS - threads.leading_zeros()) as usize;
+        utils::pairwise_sum_parallel(&self.data, depth)
+    }
+}
+
+// Equality comparisons for Matrix
+
+impl<T: Clone + Num> PartialEq for Matrix<T> {
+    fn eq(&self, other: &Matrix<T>) -> bool {
+        // A 2x3 and a 3x2 can share the same backing data, so the shapes
+        // have to match before the slices are compared
+        self.shape() == other.shape() && self.data[..] == other.data[..]
+    }
+}
+
+impl<T: Clone + Num + Eq> Eq for Matrix<T> {}
+
+// Hashes the shape together with the data, consistent with PartialEq
+
+impl<T: Hash> Hash for Matrix<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rows.hash(state);
+        self.cols.hash(state);
+        self.data.hash(state);
+    }
+}
+
+// Opt-in multiplication for element types that multiply by reference, such
+// as BigInt, where every clone in `matmul_into` is a heap allocation
+
+impl<T: Clone + Num> Matrix<T>
+where
+    for<'a> &'a T: ops::Mul<&'a T, Output = T>,
+{
+    pub fn matmul_ref_into(&self, other: &Matrix<T>, out: &mut Matrix<T>) {
+        self.assert_matmul_shapes(other, out);
+
+        let (n, p) = (self.cols, other.cols);
+        for i in 0..self.rows {
+            for j in 0..p {
+                let mut val = zero();
+                for k in 0..n {
+                    val = val + &self.data[i * n + k] * &other.data[k * p + j];
+                }
+                out.data[i * p + j] = val;
+            }
+        }
+    }
+}
+
+// Addition implementation for Matrix and &Matrix
+
+impl<T: Clone + Num> ops::Add<Matrix<T>> for Matrix<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        &self + &other
+    }
+}
+
+impl<'b, T: Clone + Num> ops::Add<&'b Matrix<T>> for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn add(self, other: &'b Matrix<T>) -> Matrix<T> {
+        self.zip_with(other, "add", |a, b| a + b)
+    }
+}
+
+// Subtraction implementation for Matrix and &Matrix
+
+impl<T: Clone + Num> ops::Sub<Matrix<T>> for Matrix<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        &self - &other
+    }
+}
+
+impl<'b, T: Clone + Num> ops::Sub<&'b Matrix<T>> for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn sub(self, other: &'b Matrix<T>) -> Matrix<T> {
+        self.zip_with(other, "sub", |a, b| a - b)
+    }
+}
+
+// Matrix multiplication implementation for Matrix and &Matrix
+
+impl<T: Clone + Num> ops::Mul<Matrix<T>> for Matrix<T> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        &self * &other
+    }
+}
+
+impl<'b, T: Clone + Num> ops::Mul<&'b Matrix<T>> for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, other: &'b Matrix<T>) -> Matrix<T> {
+        let mut result = Matrix::<T>::new(self.rows, other.cols);
+        self.matmul_into(other, &mut result);
+
+        result
+    }
+}
+
+// Macros
+
+#[macro_export]
+macro_rules! sq_matrix {
+    ($elem:expr; $n:expr) => {
+        {
+            let size = $n as usize;
+            let data = vec![$elem; size * size];
+
+            $crate::matrix::Matrix::from(size, size, data)
+        }
+    };
+
+    ( $( $x:expr ),* ) => {
+        {
+            use $crate::utils::get_integral_square_root;
+
+            let data_vec = vec![$($x),*];
+            let data_len = data_vec.len();
+
+            // Ensure that number of elements is a perfect square
+            match get_integral_square_root(data_len) {
+                Some(root) => $crate::matrix::Matrix::from(root, root, data_vec),
+                None => panic!("Number of elements must be a perfect square..")
+            }
+        }
+    };
+}
+
+// Asserts two float matrices of the same shape agree element-wise to within
+// `epsilon`, reporting the worst offending element on failure
+
+#[macro_export]
+macro_rules! assert_matrix_eq {
+    ($left:expr, $right:expr, $epsilon:expr) => {{
+        let (left, right) = (&$left, &$right);
+        let (diff, i, j) = left.max_abs_diff(right);
+
+        // A NaN difference is incomparable, and fails too
+        let within = diff.partial_cmp(&$epsilon);
+        if within.is_none() || within == Some(::std::cmp::Ordering::Greater) {
+            panic!(
+                "matrices differ by {} at ({}, {}): {} vs {} (epsilon {})",
+                diff,
+                i,
+                j,
+                left.at(i, j),
+                right.at(i, j),
+                $epsilon
+            );
+        }
+    }};
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::{Axis, Matrix, TieBreak};
+    use crate::error::{NonFiniteError, ShapeError};
+
+    #[test]
+    fn init_test() {
+        let m = 10;
+        let n = 20;
+        let mat = Matrix::<i64>::new(m, n);
+
+        for i in 0..m {
+            for j in 0..n {
+                assert_eq!(mat.at(i, j), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn identity_test() {
+        let mat = Matrix::<i64>::identity(3);
+        let expected = sq_matrix![1, 0, 0, 0, 1, 0, 0, 0, 1];
+
+        assert_eq!(mat, expected);
+    }
+
+    #[test]
+    fn fold_test() {
+        let mat = sq_matrix![1, 2, 3, 4];
+
+        assert_eq!(mat.fold(1, |acc, val| acc * val), 24);
+        assert_eq!(
+            mat.fold(String::new(), |acc, val| acc + &val.to_string()),
+            "1234"
+        );
+    }
+
+    #[test]
+    fn apply_inplace_test() {
+        let mut mat = Matrix::from(2, 2, vec![1, -2, 3, 4]);
+        let buffer = mat.data.as_ptr();
+
+        mat.apply_inplace(|val| val * 2);
+        assert_eq!(mat, Matrix::from(2, 2, vec![2, -4, 6, 8]));
+        assert_eq!(mat.data.as_ptr(), buffer);
+    }
+
+    #[test]
+    fn diag_add_test() {
+        let mat = Matrix::<i64>::identity(3).diag_add(2);
+        let expected = sq_matrix![3, 0, 0, 0, 3, 0, 0, 0, 3];
+
+        assert_eq!(mat, expected);
+    }
+
+    #[test]
+    fn sum_test() {
+        assert_eq!(Matrix::from(2, 2, vec![1, 2, 3, 4]).sum(), 10);
+        assert_eq!(Matrix::<f64>::new(0, 3).sum(), 0.);
+    }
+
+    #[test]
+    fn sum_deterministic_test() {
+        let n = 300_000;
+        let data: Vec<f64> = (0..n)
+            .map(|k| ((k * 7919) % 1000) as f64 * 1e-3 + 0.1)
+            .collect();
+        let mat = Matrix::from(n / 100, 100, data.clone());
+
+        let expected = crate::utils::pairwise_sum(&data);
+        for _ in 0..3 {
+            assert_eq!(mat.sum_deterministic().to_bits(), expected.to_bits());
+        }
+        for depth in 0..5 {
+            let parallel = crate::utils::pairwise_sum_parallel(&data, depth);
+            assert_eq!(parallel.to_bits(), expected.to_bits());
+        }
+
+        let small = Matrix::<f32>::from(2, 2, vec![1., 2., 3., 4.]);
+        assert_eq!(small.sum_deterministic(), 10.);
+    }
+
+    #[test]
+    fn assert_finite_test() {
+        let mut mat = Matrix::<f64>::from(2, 3, vec![1., 2., 3., 4., 5., 6.]);
+        assert_eq!(mat.assert_finite(), Ok(()));
+
+        mat.set(1, 1, f64::INFINITY);
+        mat.set(1, 2, f64::NAN);
+        let err = mat.assert_finite().unwrap_err();
+        assert_eq!(err, NonFiniteError { row: 1, col: 1 });
+        assert_eq!(err.to_string(), "non-finite value at (1, 1)");
+    }
+
+    #[test]
+    fn sum_kahan_test() {
+        let mut data = vec![1e-16; 10_001];
+        data[0] = 1.;
+        let mat = Matrix::<f64>::from(1, data.len(), data);
+
+        // Every tiny term is lost against the running total of 1
+        assert_eq!(mat.sum(), 1.);
+
+        let expected = 1. + 1e-12;
+        assert!((mat.sum_kahan() - expected).abs() < 1e-20);
+        assert!((mat.sum_kahan() - expected).abs() < (mat.sum() - expected).abs());
+    }
+
+    #[test]
+    fn trace_product_test() {
+        let mat1 = Matrix::from(2, 3, vec![3, 4, 5, 1, 6, 8]);
+        let mat2 = Matrix::from(3, 2, vec![6, 2, 9, 0, 3, 1]);
+
+        assert_eq!(mat1.trace_product(&mat2), (&mat1 * &mat2).trace());
+        assert_eq!(mat2.trace_product(&mat1), (&mat2 * &mat1).trace());
+    }
+
+    #[test]
+    #[should_panic(expected = "shape mismatch in trace_product: 2x3 vs 2x3")]
+    fn trace_product_shape_test() {
+        let mat = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        mat.trace_product(&mat);
+    }
+
+    #[test]
+    fn from_fn_test() {
+        let mat = Matrix::from_fn(2, 3, |i, j| i * 10 + j);
+        assert_eq!(mat, Matrix::from(2, 3, vec![0, 1, 2, 10, 11, 12]));
+
+        assert_eq!(Matrix::<i32>::from_fn(0, 4, |_, _| 1).shape(), (0, 4));
+    }
+
+    #[test]
+    fn fill_test() {
+        let mut mat = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let buffer = mat.data.as_ptr();
+
+        mat.fill(7);
+        assert_eq!(mat, Matrix::filled(2, 3, 7));
+        assert_eq!(mat.data.as_ptr(), buffer);
+    }
+
+    #[test]
+    fn setter_test() {
+        let mut mat = Matrix::<i64>::new(2, 2);
+
+        mat.set(0, 0, 4);
+        mat.set(0, 1, 5);
+        mat.set(1, 0, 6);
+        mat.set(1, 1, 7);
+
+        assert_eq!(mat.at(0, 0), 4);
+        assert_eq!(mat.at(0, 1), 5);
+        assert_eq!(mat.at(1, 0), 6);
+        assert_eq!(mat.at(1, 1), 7);
+    }
+
+    #[test]
+    fn map_test() {
+        let mat = sq_matrix![4, 5, 6, 7];
+        let expected = sq_matrix![8, 10, 12, 14];
+
+        let new_mat = mat.map(|val| val * 2);
+        assert_eq!(new_mat, expected);
+    }
+
+    #[test]
+    fn map_with_test() {
+        let mat1 = sq_matrix![1, 2, 3, 4];
+        let mat2 = sq_matrix![10, 20, 30, 40];
+
+        let expected = sq_matrix![11, 22, 33, 44];
+
+        let new_mat = mat1.map_with(mat2, |a, b| a + b);
+        assert_eq!(new_mat, expected);
+    }
+
+    #[test]
+    fn addition_test() {
+        let mat1 = sq_matrix![10, 20, 30, 40];
+        let mat2 = sq_matrix![1, 2, 3, 4];
+
+        let expected = sq_matrix![11, 22, 33, 44];
+
+        // By reference
+        let new_mat = &mat1 + &mat2;
+        assert_eq!(new_mat, expected);
+
+        // By value
+        let new_mat = mat1 + mat2;
+        assert_eq!(new_mat, expected);
+    }
+
+    #[test]
+    fn subtraction_test() {
+        let mat1 = sq_matrix![11, 22, 33, 44];
+        let mat2 = sq_matrix![1, 2, 3, 4];
+
+        let expected = sq_matrix![10, 20, 30, 40];
+
+        // By reference
+        let new_mat = &mat1 - &mat2;
+        assert_eq!(new_mat, expected);
+
+        // By value
+        let new_mat = mat1 - mat2;
+        assert_eq!(new_mat, expected);
+    }
+
+    #[test]
+    fn multiplication_test() {
+        let mat1 = Matrix::from(2, 3, vec![3, 4, 5, 1, 6, 8]);
+        let mat2 = Matrix::from(3, 2, vec![6, 2, 9, 0, 3, 1]);
+
+        let expected = sq_matrix![69, 11, 84, 10];
+
+        // By reference
+        let new_mat = &mat1 * &mat2;
+        assert_eq!(new_mat, expected);
+
+        // By value
+        let new_mat = mat1 * mat2;
+        assert_eq!(new_mat, expected);
+    }
+
+    // Integers mod 7. Anything that implements Clone and the num traits
+    // works as an element type: Num brings Zero, One, the five arithmetic
+    // operators and from_str_radix, and nothing else is required
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Mod7(u32);
+
+    macro_rules! mod7_op {
+        ($trait:ident, $method:ident, |$a:ident, $b:ident| $body:expr) => {
+            impl std::ops::$trait for Mod7 {
+                type Output = Mod7;
+
+                fn $method(self, other: Mod7) -> Mod7 {
+                    let ($a, $b) = (self.0, other.0);
+                    Mod7($body % 7)
+                }
+            }
+        };
+    }
+
+    mod7_op!(Add, add, |a, b| a + b);
+    mod7_op!(Sub, sub, |a, b| a + 7 - b);
+    mod7_op!(Mul, mul, |a, b| a * b);
+    // Division multiplies by the inverse b^5, and in a field every non-zero
+    // value divides exactly, leaving no remainder
+    mod7_op!(Div, div, |a, b| a * b.pow(5));
+    mod7_op!(Rem, rem, |_a, _b| 0);
+
+    impl num::Zero for Mod7 {
+        fn zero() -> Self {
+            Mod7(0)
+        }
+
+        fn is_zero(&self) -> bool {
+            self.0 == 0
+        }
+    }
+
+    impl num::One for Mod7 {
+        fn one() -> Self {
+            Mod7(1)
+        }
+    }
+
+    impl num::Num for Mod7 {
+        type FromStrRadixErr = std::num::ParseIntError;
+
+        fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+            u32::from_str_radix(s, radix).map(|val| Mod7(val % 7))
+        }
+    }
+
+    #[test]
+    fn custom_element_test() {
+        let m = |vals: Vec<u32>| vals.into_iter().map(Mod7).collect::<Vec<_>>();
+
+        let mat1 = Matrix::from(2, 2, m(vec![3, 4, 5, 6]));
+        let mat2 = Matrix::from(2, 2, m(vec![2, 1, 0, 3]));
+
+        // [[6, 15], [10, 23]] reduced mod 7
+        assert_eq!(&mat1 * &mat2, Matrix::from(2, 2, m(vec![6, 1, 3, 2])));
+        assert_eq!(&mat1 + &mat2, Matrix::from(2, 2, m(vec![5, 5, 5, 2])));
+        assert_eq!(&mat1 - &mat2, Matrix::from(2, 2, m(vec![1, 3, 5, 3])));
+        assert_eq!(&mat1 * &Matrix::identity(2), mat1);
+
+        // Dividing by the matrix undoes the element-wise product
+        let ones = Matrix::filled(2, 2, Mod7(1));
+        assert_eq!(mat1.hadamard(&mat1).hadamard_div(&mat1), mat1);
+        assert_eq!(mat1.hadamard_div(&mat1), ones);
+    }
+
+    #[test]
+    fn bigint_mul_test() {
+        use num::BigInt;
+
+        let big = |val: i64| BigInt::from(val) << 80;
+        let mat1 = Matrix::from(2, 3, vec![big(3), big(4), big(5), big(1), big(6), big(8)]);
+        let mat2 = Matrix::from(
+            3,
+            1,
+            vec![BigInt::from(6), BigInt::from(9), BigInt::from(3)],
+        );
+
+        let product = &mat1 * &mat2;
+        assert_eq!(product, Matrix::from(2, 1, vec![big(69), big(84)]));
+
+        // The cloning and borrowing loops agree
+        let mut borrowed = Matrix::new(2, 1);
+        mat1.matmul_ref_into(&mat2, &mut borrowed);
+        assert_eq!(borrowed, product);
+    }
+
+    #[test]
+    fn transpose_into_test() {
+        let mat = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let mut out = Matrix::filled(3, 2, 9);
+
+        mat.transpose_into(&mut out);
+        assert_eq!(out, mat.transpose());
+    }
+
+    #[test]
+    fn matmul_into_test() {
+        let mat1 = Matrix::from(2, 3, vec![3, 4, 5, 1, 6, 8]);
+        let mat2 = Matrix::from(3, 2, vec![6, 2, 9, 0, 3, 1]);
+
+        let mut out = Matrix::<i32>::new(2, 2);
+        let buffer = out.data.as_ptr();
+
+        mat1.matmul_into(&mat2, &mut out);
+        assert_eq!(out, &mat1 * &mat2);
+
+        // Stale contents are overwritten, not accumulated into
+        mat1.matmul_into(&mat2, &mut out);
+        assert_eq!(out, &mat1 * &mat2);
+        assert_eq!(out.data.as_ptr(), buffer);
+    }
+
+    #[test]
+    fn try_mul_test() {
+        let mat1 = Matrix::from(2, 3, vec![3, 4, 5, 1, 6, 8]);
+        let mat2 = Matrix::from(3, 2, vec![6, 2, 9, 0, 3, 1]);
+
+        assert_eq!(mat1.try_mul(&mat2), Ok(&mat1 * &mat2));
+
+        let err = mat1.try_mul(&mat1).unwrap_err();
+        assert_eq!(
+            err,
+            ShapeError::Mismatch {
+                op: "mul",
+                left: (2, 3),
+                right: (2, 3),
+            }
+        );
+        assert_eq!(err.to_string(), "shape mismatch in mul: 2x3 vs 2x3");
+    }
+
+    #[test]
+    fn gram_test() {
+        let mat = Matrix::from(3, 2, vec![1., 2., 3., 4., 5., 6.]);
+
+        assert_eq!(mat.gram(), &mat * &mat.transpose());
+    }
+
+    #[test]
+    fn matmul_acc_test() {
+        // Every dot product here exceeds i8::MAX
+        let mat1 = Matrix::<i8>::from(2, 2, vec![100, 100, -100, 50]);
+        let mat2 = Matrix::<i8>::from(2, 2, vec![100, 5, 100, 3]);
+
+        let expected = Matrix::<i32>::from(2, 2, vec![20000, 800, -5000, -350]);
+
+        let new_mat = mat1.matmul_acc::<i32>(&mat2);
+        assert_eq!(new_mat, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "shape mismatch in matmul_acc: 2x3 vs 2x3")]
+    fn matmul_acc_shape_test() {
+        let mat = Matrix::<i8>::new(2, 3);
+        mat.matmul_acc::<i32>(&mat);
+    }
+
+    #[test]
+    fn hadamard_test() {
+        let mat1 = sq_matrix![10, 20, 30, 40];
+        let mat2 = sq_matrix![2, 4, 5, 8];
+
+        assert_eq!(mat1.hadamard(&mat2), sq_matrix![20, 80, 150, 320]);
+        assert_eq!(mat1.hadamard_div(&mat2), sq_matrix![5, 5, 6, 5]);
+
+        // Floats follow IEEE semantics on division by zero
+        let numerator = Matrix::<f64>::from(1, 2, vec![1., 0.]);
+        let quotient = numerator.hadamard_div(&Matrix::new(1, 2));
+        assert!(quotient.at(0, 0).is_infinite());
+        assert!(quotient.at(0, 1).is_nan());
+    }
+
+    #[test]
+    fn float_math_test() {
+        let mat = Matrix::<f64>::from(1, 2, vec![4.0, 9.0]);
+
+        assert_eq!(mat.sqrt(), Matrix::from(1, 2, vec![2.0, 3.0]));
+        assert_eq!(mat.powf(2.0), Matrix::from(1, 2, vec![16.0, 81.0]));
+        let round_trip = mat.ln().exp();
+        for (a, b) in round_trip.data.iter().zip(mat.data.iter()) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        assert_eq!(mat.map(|val| -val).abs(), mat);
+    }
+
+    #[test]
+    fn broadcast_test() {
+        let mat = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+
+        // Row vector is repeated down every row
+        let row = Matrix::from(1, 3, vec![10, 20, 30]);
+        let expected = Matrix::from(2, 3, vec![11, 22, 33, 14, 25, 36]);
+        assert_eq!(mat.add_broadcast(&row), expected);
+
+        // Column vector is repeated across every column
+        let col = Matrix::from(2, 1, vec![3, 6]);
+        let expected = Matrix::from(2, 3, vec![-2, -1, 0, -2, -1, 0]);
+        assert_eq!(mat.sub_broadcast(&col), expected);
+    }
+
+    #[test]
+    fn mul_div_broadcast_test() {
+        let mat = Matrix::<f64>::from(2, 3, vec![1., 2., 3., 4., 6., 10.]);
+
+        // Normalize each row by its sum
+        let sums = mat.sum_axis(Axis::Row);
+        assert_eq!(
+            mat.div_broadcast(&sums),
+            Matrix::from(2, 3, vec![1. / 6., 2. / 6., 3. / 6., 0.2, 0.3, 0.5])
+        );
+
+        let scale = Matrix::from(1, 3, vec![2., 0., -1.]);
+        assert_eq!(
+            mat.mul_broadcast(&scale),
+            Matrix::from(2, 3, vec![2., 0., -3., 8., 0., -10.])
+        );
+        assert_eq!(
+            mat.mul_broadcast(&Matrix::from(1, 1, vec![0.5])).at(1, 2),
+            5.
+        );
+    }
+
+    #[test]
+    fn bool_reduction_test() {
+        let mask = Matrix::from(2, 3, vec![true, false, true, false, true, false]);
+        assert_eq!(mask.count_true(), 3);
+        assert!(mask.any());
+        assert!(!mask.all());
+
+        let none = Matrix::from(1, 2, vec![false, false]);
+        assert_eq!(none.count_true(), 0);
+        assert!(!none.any());
+
+        let every = Matrix::from(1, 2, vec![true, true]);
+        assert!(every.all());
+    }
+
+    #[test]
+    fn map_cols_test() {
+        let mat = Matrix::from(3, 2, vec![1., 10., 2., 20., 3., 60.]);
+
+        let standardized = mat.map_cols(|col| {
+            let n = col.len() as f64;
+            let mean = col.iter().sum::<f64>() / n;
+            let std = (col.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt();
+            col.iter().map(|x| (x - mean) / std).collect()
+        });
+
+        let means = standardized.mean_axis(Axis::Col);
+        let squares = standardized.hadamard(&standardized).mean_axis(Axis::Col);
+        for j in 0..2 {
+            assert!(means.at(0, j).abs() < 1e-12);
+            assert!((squares.at(0, j) - 1.).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn max_with_index_test() {
+        let logits = Matrix::from(3, 3, vec![0.1, 2.5, -1., 3., 3., 0., -2., -1., -4.]);
+
+        let (values, indices) = logits.max_with_index_axis(Axis::Row);
+        assert_eq!(values, Matrix::from(3, 1, vec![2.5, 3., -1.]));
+        assert_eq!(indices, vec![1, 0, 1]);
+
+        let (values, indices) = logits.max_with_index_axis(Axis::Col);
+        assert_eq!(values, Matrix::from(1, 3, vec![3., 3., 0.]));
+        assert_eq!(indices, vec![1, 1, 1]);
+
+        assert_eq!(
+            logits.max_axis(Axis::Row),
+            Matrix::from(3, 1, vec![2.5, 3., -1.])
+        );
+        assert_eq!(logits.argmax_axis(Axis::Row), vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn minimum_maximum_test() {
+        let mat1 = Matrix::from(1, 3, vec![1, 5, 2]);
+        let mat2 = Matrix::from(1, 3, vec![3, 4, 6]);
+
+        assert_eq!(mat1.minimum(&mat2), Matrix::from(1, 3, vec![1, 4, 2]));
+        assert_eq!(mat1.maximum(&mat2), Matrix::from(1, 3, vec![3, 5, 6]));
+
+        // ReLU against a zero matrix
+        let signed = Matrix::from(1, 3, vec![-1., 0.5, -0.2]);
+        let relu = signed.maximum(&Matrix::new(1, 3));
+        assert_eq!(relu, Matrix::from(1, 3, vec![0., 0.5, 0.]));
+    }
+
+    #[test]
+    fn topk_rows_test() {
+        let scores = Matrix::from(2, 5, vec![0.1, 0.9, 0.3, 0.7, 0.3, 5., 1., 5., 2., 0.]);
+
+        let topk = scores.topk_rows(3);
+        assert_eq!(topk[0], vec![1, 3, 2]);
+        assert_eq!(topk[1], vec![0, 2, 3]);
+
+        let scores = Matrix::from(1, 4, vec![f64::NAN, 2., f64::NAN, 3.]);
+        assert_eq!(scores.topk_rows(4), vec![vec![3, 1, 0, 2]]);
+    }
+
+    #[test]
+    fn col_major_test() {
+        let mat = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+
+        let col_major = mat.to_col_major();
+        assert_eq!(col_major, vec![1, 4, 2, 5, 3, 6]);
+
+        let round_trip = Matrix::from_col_major(2, 3, col_major);
+        assert_eq!(round_trip.shape(), (2, 3));
+        assert_eq!(round_trip, mat);
+    }
+
+    #[test]
+    #[should_panic(expected = "shape mismatch in add: 2x3 vs 3x2")]
+    fn add_shape_mismatch_test() {
+        let _ = Matrix::<i32>::new(2, 3) + Matrix::<i32>::new(3, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "shape mismatch in mul: 2x3 vs 2x3")]
+    fn mul_shape_mismatch_test() {
+        let _ = Matrix::<i32>::new(2, 3) * Matrix::<i32>::new(2, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "shape mismatch in map_with: 1x2 vs 2x1")]
+    fn map_with_shape_mismatch_test() {
+        let _ = Matrix::<i32>::new(1, 2).map_with(Matrix::new(2, 1), |a, b| a + b);
+    }
+
+    #[test]
+    fn iter_indexed_test() {
+        let mat = sq_matrix![1, 2, 3, 4];
+
+        let triples: Vec<_> = mat.iter_indexed().collect();
+        assert_eq!(
+            triples,
+            vec![(0, 0, &1), (0, 1, &2), (1, 0, &3), (1, 1, &4)]
+        );
+    }
+
+    #[test]
+    fn tile_test() {
+        let mat = sq_matrix![1, 2, 3, 4];
+
+        let vertical = mat.tile(2, 1);
+        assert_eq!(vertical.shape(), (4, 2));
+        assert_eq!(vertical, Matrix::from(4, 2, vec![1, 2, 3, 4, 1, 2, 3, 4]));
+
+        let grid = Matrix::from(1, 2, vec![1, 2]).tile(2, 2);
+        assert_eq!(grid, Matrix::from(2, 4, vec![1, 2, 1, 2, 1, 2, 1, 2]));
+    }
+
+    #[test]
+    fn pad_test() {
+        let mat = sq_matrix![1, 2, 3, 4];
+
+        let padded = mat.pad(1, 1, 1, 1, 0);
+        let expected = sq_matrix![0, 0, 0, 0, 0, 1, 2, 0, 0, 3, 4, 0, 0, 0, 0, 0];
+        assert_eq!(padded.shape(), (4, 4));
+        assert_eq!(padded, expected);
+
+        let uneven = mat.pad(0, 1, 2, 0, 9);
+        assert_eq!(
+            uneven,
+            Matrix::from(3, 4, vec![9, 9, 1, 2, 9, 9, 3, 4, 9, 9, 9, 9])
+        );
+    }
+
+    #[test]
+    fn flip_rotate_test() {
+        let mat = sq_matrix![1, 2, 3, 4];
+
+        assert_eq!(mat.flip_horizontal(), sq_matrix![2, 1, 4, 3]);
+        assert_eq!(mat.flip_vertical(), sq_matrix![3, 4, 1, 2]);
+        assert_eq!(mat.rot90(), sq_matrix![2, 4, 1, 3]);
+        assert_eq!(mat.rot90().rot90().rot90().rot90(), mat);
+
+        let wide = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let rotated = wide.rot90();
+        assert_eq!(rotated.shape(), (3, 2));
+        assert_eq!(rotated, Matrix::from(3, 2, vec![3, 6, 2, 5, 1, 4]));
+    }
+
+    #[test]
+    fn select_test() {
+        let mask = sq_matrix![true, false, false, true];
+        let ones = Matrix::<i32>::filled(2, 2, 1);
+        let zeros = Matrix::<i32>::new(2, 2);
+
+        assert_eq!(Matrix::select(&mask, &ones, &zeros), sq_matrix![1, 0, 0, 1]);
+        assert_eq!(Matrix::select(&mask, &zeros, &ones), sq_matrix![0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn safe_hadamard_div_test() {
+        let mat1 = Matrix::<f64>::from(1, 3, vec![1., 0., -2.]);
+        let mat2 = Matrix::from(1, 3, vec![0., 0., 4.]);
+
+        let quotient = mat1.safe_hadamard_div(&mat2, 1e-8);
+        assert!(quotient.data.iter().all(|val| val.is_finite()));
+        assert_eq!(quotient.at(0, 0), 1e8);
+        assert_eq!(quotient.at(0, 1), 0.);
+        assert!((quotient.at(0, 2) + 0.5).abs() < 1e-8);
+    }
+
+    #[test]
+    fn max_abs_diff_test() {
+        let mat1 = Matrix::<f64>::from(2, 2, vec![1., 2., 3., 4.]);
+        let mat2 = Matrix::from(2, 2, vec![1.001, 2., 2.98, 4.01]);
+
+        let (diff, i, j) = mat1.max_abs_diff(&mat2);
+        assert!((diff - 0.02).abs() < 1e-12);
+        assert_eq!((i, j), (1, 0));
+
+        assert_eq!(mat1.max_abs_diff(&mat1), (0., 0, 0));
+
+        let nan = Matrix::from(2, 2, vec![1., f64::NAN, 3., 4.]);
+        let (diff, i, j) = mat1.max_abs_diff(&nan);
+        assert!(diff.is_nan());
+        assert_eq!((i, j), (0, 1));
+    }
+
+    #[test]
+    fn assert_matrix_eq_test() {
+        let mat1 = Matrix::<f64>::from(2, 2, vec![1., 2., 3., 4.]);
+        let mat2 = Matrix::from(2, 2, vec![1. + 1e-10, 2., 3., 4. - 1e-10]);
+
+        assert_matrix_eq!(mat1, mat2, 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "matrices differ by 0.5 at (1, 0): 3 vs 3.5 (epsilon 0.001)")]
+    fn assert_matrix_eq_panic_test() {
+        let mat1 = Matrix::<f64>::from(2, 2, vec![1., 2., 3., 4.]);
+        let mat2 = Matrix::from(2, 2, vec![1., 2.25, 3.5, 4.]);
+
+        assert_matrix_eq!(mat1, mat2, 1e-3);
+    }
+
+    #[test]
+    fn activation_test() {
+        let mat = Matrix::<f32>::from(1, 3, vec![-1000., 0., 2.]);
+
+        assert_eq!(mat.relu(), Matrix::from(1, 3, vec![0., 0., 2.]));
+        assert_eq!(mat.sigmoid().at(0, 0), 0.);
+        assert_eq!(mat.sigmoid().at(0, 1), 0.5);
+        assert_eq!(mat.tanh().at(0, 0), -1.);
+        assert!(mat.sigmoid().data.iter().all(|val| val.is_finite()));
+    }
+
+    #[test]
+    fn logsumexp_axis_test() {
+        let mat = Matrix::<f64>::from(2, 3, vec![0.5, -1., 2., 1., 0., 3.]);
+        let naive = |vals: &[f64]| vals.iter().map(|val| val.exp()).sum::<f64>().ln();
+
+        let rows = mat.logsumexp_axis(Axis::Row);
+        assert_eq!(rows.shape(), (2, 1));
+        assert!((rows.at(0, 0) - naive(&[0.5, -1., 2.])).abs() < 1e-12);
+        assert!((rows.at(1, 0) - naive(&[1., 0., 3.])).abs() < 1e-12);
+
+        let cols = mat.logsumexp_axis(Axis::Col);
+        assert_eq!(cols.shape(), (1, 3));
+        assert!((cols.at(0, 2) - naive(&[2., 3.])).abs() < 1e-12);
+
+        let large = Matrix::<f64>::from(1, 2, vec![1000., 1000.]);
+        assert!((large.logsumexp_axis(Axis::Row).at(0, 0) - (1000. + 2f64.ln())).abs() < 1e-9);
+
+        let blocked = Matrix::from(1, 2, vec![f64::NEG_INFINITY; 2]);
+        assert_eq!(
+            blocked.logsumexp_axis(Axis::Row).at(0, 0),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn softmax_test() {
+        let mat = Matrix::<f64>::from(2, 3, vec![1., 2., 3., 1000., 1000., -1000.]);
+
+        let probs = mat.softmax();
+        for i in 0..2 {
+            let total: f64 = (0..3).map(|j| probs.at(i, j)).sum();
+            assert!((total - 1.).abs() < 1e-12);
+        }
+        assert!((probs.at(0, 2) / probs.at(0, 1) - 1f64.exp()).abs() < 1e-12);
+        assert_eq!(probs.at(1, 0), 0.5);
+        assert_eq!(probs.at(1, 2), 0.);
+    }
+
+    #[test]
+    fn softmax_temperature_test() {
+        let mat = Matrix::<f64>::from(1, 3, vec![1., 2., 1.5]);
+
+        let sharp = mat.softmax_temperature(0.01);
+        assert!(sharp.at(0, 1) > 1. - 1e-12);
+        assert!(sharp.at(0, 0) < 1e-12 && sharp.at(0, 2) < 1e-12);
+
+        let flat = mat.softmax_temperature(1e6);
+        for j in 0..3 {
+            assert!((flat.at(0, j) - 1. / 3.).abs() < 1e-6);
+        }
+
+        assert_eq!(mat.softmax_temperature(1.), mat.softmax());
+    }
+
+    #[test]
+    fn masked_softmax_test() {
+        let mat = Matrix::<f64>::from(2, 4, vec![1., 2., 3., 4., 1., 1., 1., 1.]);
+        let mask = Matrix::from(
+            2,
+            4,
+            vec![true, false, true, false, false, false, false, false],
+        );
+
+        let probs = mat.masked_softmax(&mask);
+        assert_eq!(probs.at(0, 1), 0.);
+        assert_eq!(probs.at(0, 3), 0.);
+
+        let expected = Matrix::<f64>::from(1, 2, vec![1., 3.]).softmax();
+        assert!((probs.at(0, 0) - expected.at(0, 0)).abs() < 1e-12);
+        assert!((probs.at(0, 2) - expected.at(0, 1)).abs() < 1e-12);
+
+        // Nothing left to attend to
+        assert!((0..4).all(|j| probs.at(1, j) == 0.));
+    }
+
+    #[test]
+    fn at_ref_test() {
+        let mat = Matrix::from(2, 2, vec![1, 2, 3, 4]);
+
+        assert_eq!(mat.at_ref(1, 0), &3);
+        assert_eq!(*mat.at_ref(0, 1), mat.at(0, 1));
+    }
+
+    #[test]
+    fn monitor_stats_test() {
+        let mat = Matrix::<f64>::from(2, 4, vec![0., -2., 0., 4., f64::NAN, 0., 1., 3.]);
+
+        assert_eq!(mat.frac_zeros(), 3. / 8.);
+        assert_eq!(mat.frac_nan(), 1. / 8.);
+        assert!(mat.mean_abs().is_nan());
+
+        let ints = Matrix::from(1, 4, vec![0, -2, 0, 6]);
+        assert_eq!(ints.mean_abs(), 2.);
+        assert_eq!(ints.frac_zeros(), 0.5);
+        assert_eq!(ints.frac_nan(), 0.);
+
+        for empty in [Matrix::<f64>::new(0, 0), Matrix::<f64>::new(0, 3)] {
+            assert_eq!(empty.mean_abs(), 0.);
+            assert_eq!(empty.frac_zeros(), 0.);
+            assert_eq!(empty.frac_nan(), 0.);
+        }
+    }
+
+    #[test]
+    fn histogram_test() {
+        let mut rng = crate::random::SplitMix64::new(4);
+        let uniform = Matrix::random_uniform(100, 100, 0., 1., &mut rng);
+
+        let (counts, edges) = uniform.histogram(4, Some((0., 1.)));
+        assert_eq!(edges, vec![0., 0.25, 0.5, 0.75, 1.]);
+        assert_eq!(counts.iter().sum::<usize>(), 10_000);
+        assert!(counts
+            .iter()
+            .all(|&count| (count as f64 - 2500.).abs() < 150.));
+
+        let mat = Matrix::from(1, 5, vec![1, 2, 2, 3, 5]);
+        let (counts, edges) = mat.histogram(2, None);
+        assert_eq!(edges, vec![1., 3., 5.]);
+        assert_eq!(counts, vec![3, 2]);
+
+        let (counts, _) = mat.histogram(2, Some((2., 4.)));
+        assert_eq!(counts, vec![2, 1]);
+    }
+
+    #[test]
+    fn row_norms_test() {
+        let mat = Matrix::from(2, 2, vec![3, 4, 0, 5]);
+        assert_eq!(mat.row_norms(), Matrix::from(2, 1, vec![5., 5.]));
+
+        let mat = Matrix::from(2, 2, vec![3, 4, 0, 0]);
+        assert_eq!(
+            mat.normalize_rows(),
+            Matrix::from(2, 2, vec![0.6, 0.8, 0., 0.])
+        );
+    }
+
+    #[test]
+    fn submatrix_test() {
+        let mat = Matrix::from(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        assert_eq!(
+            mat.submatrix(1..3, 0..2),
+            Matrix::from(2, 2, vec![4, 5, 7, 8])
+        );
+        assert_eq!(mat.submatrix(0..0, 0..3).shape(), (0, 3));
+    }
+
+    #[test]
+    fn select_cols_test() {
+        let mat = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(
+            mat.select_cols(&[2, 0]),
+            Matrix::from(2, 2, vec![3, 1, 6, 4])
+        );
+        assert_eq!(
+            mat.select_cols(&[1, 1]),
+            Matrix::from(2, 2, vec![2, 2, 5, 5])
+        );
+        assert_eq!(
+            mat.select_rows(&[1, 0, 1]),
+            Matrix::from(3, 3, vec![4, 5, 6, 1, 2, 3, 4, 5, 6])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Column 3 out of range for 3 columns..")]
+    fn select_cols_range_test() {
+        Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]).select_cols(&[0, 3]);
+    }
+
+    #[test]
+    fn concat_test() {
+        let columns = [
+            Matrix::from(2, 1, vec![1, 4]),
+            Matrix::from(2, 1, vec![2, 5]),
+            Matrix::from(2, 1, vec![3, 6]),
+        ];
+        assert_eq!(
+            Matrix::concat(&columns, Axis::Row).unwrap(),
+            Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6])
+        );
+        assert_eq!(
+            Matrix::concat(&columns, Axis::Col).unwrap(),
+            Matrix::from(6, 1, vec![1, 4, 2, 5, 3, 6])
+        );
+
+        let ragged = [Matrix::from(2, 1, vec![1, 2]), Matrix::from(1, 1, vec![3])];
+        assert_eq!(
+            Matrix::concat(&ragged, Axis::Row).unwrap_err(),
+            ShapeError::Mismatch {
+                op: "concat",
+                left: (2, 1),
+                right: (1, 1),
+            }
+        );
+
+        assert_eq!(
+            Matrix::<i32>::concat(&[], Axis::Col).unwrap_err(),
+            ShapeError::Empty { op: "concat" }
+        );
+    }
+
+    #[test]
+    fn windows_test() {
+        let mat = Matrix::from(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        let windows: Vec<_> = mat.windows(2, 2, 1).collect();
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[0], Matrix::from(2, 2, vec![1, 2, 4, 5]));
+        assert_eq!(windows[3], Matrix::from(2, 2, vec![5, 6, 8, 9]));
+
+        assert_eq!(mat.windows(2, 2, 2).count(), 1);
+        assert_eq!(mat.windows(1, 3, 1).count(), 3);
+        assert_eq!(mat.windows(4, 2, 1).count(), 0);
+    }
+
+    #[test]
+    fn split_test() {
+        let mat = Matrix::from(4, 2, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let (top, bottom) = mat.split_at_row(1);
+        assert_eq!(top, Matrix::from(1, 2, vec![1, 2]));
+        assert_eq!(bottom, Matrix::from(3, 2, vec![3, 4, 5, 6, 7, 8]));
+
+        let (left, right) = mat.split_at_col(1);
+        assert_eq!(left, Matrix::from(4, 1, vec![1, 3, 5, 7]));
+        assert_eq!(right, Matrix::from(4, 1, vec![2, 4, 6, 8]));
+
+        let (empty, all) = mat.split_at_row(0);
+        assert_eq!(empty.shape(), (0, 2));
+        assert_eq!(all, mat);
+    }
+
+    #[test]
+    fn shape_aware_eq_test() {
+        let wide = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let tall = Matrix::from(3, 2, vec![1, 2, 3, 4, 5, 6]);
+
+        assert_ne!(wide, tall);
+        assert_eq!(wide, wide.clone());
+    }
+
+    #[test]
+    fn hash_test() {
+        use std::collections::HashMap;
+
+        let wide = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let tall = Matrix::from(3, 2, vec![1, 2, 3, 4, 5, 6]);
+
+        let mut cache = HashMap::new();
+        cache.insert(wide.clone(), "wide");
+        cache.insert(tall.clone(), "tall");
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&wide), Some(&"wide"));
+        assert_eq!(
+            cache.get(&Matrix::from(3, 2, vec![1, 2, 3, 4, 5, 6])),
+            Some(&"tall")
+        );
+        assert_eq!(cache.get(&Matrix::from(1, 6, vec![1, 2, 3, 4, 5, 6])), None);
+    }
+
+    #[test]
+    fn polynomial_features_test() {
+        let single = Matrix::from(2, 1, vec![2, -3]);
+        assert_eq!(
+            single.polynomial_features(2),
+            Matrix::from(2, 3, vec![1., 2., 4., 1., -3., 9.])
+        );
+
+        let pair = Matrix::from(1, 2, vec![2, 3]);
+        assert_eq!(
+            pair.polynomial_features(2),
+            Matrix::from(1, 6, vec![1., 2., 3., 4., 6., 9.])
+        );
+        assert_eq!(pair.polynomial_features(3).cols, 10);
+        assert_eq!(pair.polynomial_features(0), Matrix::from(1, 1, vec![1.]));
+    }
+
+    #[test]
+    fn argmax_tie_break_test() {
+        let mat = Matrix::from(2, 4, vec![1, 5, 2, 5, 3, 0, 0, 0]);
+
+        assert_eq!(mat.argmax_axis_with(Axis::Row, TieBreak::First), vec![1, 0]);
+        assert_eq!(mat.argmax_axis_with(Axis::Row, TieBreak::Last), vec![3, 0]);
+        assert_eq!(
+            mat.argmax_axis_with(Axis::Row, TieBreak::First),
+            mat.argmax_axis(Axis::Row)
+        );
+        assert_eq!(
+            mat.argmax_axis_with(Axis::Col, TieBreak::Last),
+            vec![1, 0, 0, 0]
+        );
+
+        let mut rng = crate::random::SplitMix64::new(1);
+        let mut seen = [false; 4];
+        for _ in 0..50 {
+            let picks = mat.argmax_axis_with(Axis::Row, TieBreak::Random(&mut rng));
+            assert!(picks[0] == 1 || picks[0] == 3);
+            assert_eq!(picks[1], 0);
+            seen[picks[0]] = true;
+        }
+        assert!(seen[1] && seen[3]);
+
+        let nan = Matrix::from(1, 2, vec![f64::NAN, 1.]);
+        assert_eq!(
+            nan.argmax_axis_with(Axis::Row, TieBreak::Last),
+            nan.argmax_axis(Axis::Row)
+        );
+    }
+
+    #[test]
+    fn transpose_test() {
+        let mat = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let expected = Matrix::from(3, 2, vec![1, 4, 2, 5, 3, 6]);
+
+        assert_eq!(mat.transpose(), expected);
+    }
+
+    #[test]
+    fn norm_test() {
+        assert_eq!(sq_matrix![1, 2, 2, 4].norm(), 5.);
+        assert_eq!(Matrix::<f64>::new(2, 3).norm(), 0.);
+    }
+
+    #[test]
+    fn symmetric_test() {
+        let sym = sq_matrix![1., 2., 3., 2., 4., 5., 3., 5., 6.];
+        assert!(sym.is_symmetric(1e-9));
+
+        let mut asym = sym.clone();
+        asym.set(0, 2, 3.5);
+        assert!(!asym.is_symmetric(1e-9));
+
+        let expected = sq_matrix![1., 2., 3.25, 2., 4., 5., 3.25, 5., 6.];
+        assert_eq!(asym.symmetrize(), expected);
+        assert!(asym.symmetrize().is_symmetric(1e-9));
+    }
+
+    #[test]
+    fn axis_reduction_test() {
+        let mat = Matrix::from(2, 3, vec![1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(mat.sum_axis(Axis::Row), Matrix::from(2, 1, vec![6, 15]));
+        assert_eq!(mat.sum_axis(Axis::Col), Matrix::from(1, 3, vec![5, 7, 9]));
+
+        assert_eq!(mat.mean_axis(Axis::Row), Matrix::from(2, 1, vec![2., 5.]));
+        assert_eq!(
+            mat.mean_axis(Axis::Col),
+            Matrix::from(1, 3, vec![2.5, 3.5, 4.5])
+        );
+    }
+
+    #[test]
+    fn variance_test() {
+        let mat = Matrix::from(4, 2, vec![2, 1, 4, 1, 4, 1, 6, 1]);
+
+        // Column 0 has mean 4 and squared deviations summing to 8
+        let sample = mat.var_axis(Axis::Col, 1);
+        assert_eq!(sample, Matrix::from(1, 2, vec![8. / 3., 0.]));
+
+        let population = mat.var_axis(Axis::Col, 0);
+        assert_eq!(population, Matrix::from(1, 2, vec![2., 0.]));
+
+        let std = mat.std_axis(Axis::Col, 0);
+        assert_eq!(std, Matrix::from(1, 2, vec![2f64.sqrt(), 0.]));
+
+        assert_eq!(
+            mat.var_axis(Axis::Row, 0),
+            Matrix::from(4, 1, vec![0.25, 2.25, 2.25, 6.25])
+        );
+    }
+
+    #[test]
+    fn percentile_test() {
+        let row = Matrix::from(1, 3, vec![3, 1, 2]);
+        assert_eq!(row.median_axis(Axis::Row), Matrix::from(1, 1, vec![2.]));
+
+        let mat = Matrix::from(4, 2, vec![1, 40, 2, 30, 3, 20, 4, 10]);
+        assert_eq!(
+            mat.median_axis(Axis::Col),
+            Matrix::from(1, 2, vec![2.5, 25.])
+        );
+        assert_eq!(
+            mat.percentile_axis(Axis::Col, 0.25),
+            Matrix::from(1, 2, vec![1.75, 17.5])
+        );
+        assert_eq!(
+            mat.percentile_axis(Axis::Row, 1.),
+            Matrix::from(4, 1, vec![40., 30., 20., 10.])
+        );
+
+        let mat = Matrix::from(2, 2, vec![1., f64::NAN, 3., 4.]);
+        let medians = mat.median_axis(Axis::Col);
+        assert_eq!(medians.at(0, 0), 2.);
+        assert!(medians.at(0, 1).is_nan());
+    }
+
+    #[test]
+    fn cumsum_test() {
+        let row = Matrix::from(1, 4, vec![1, 2, 3, 4]);
+        assert_eq!(
+            row.cumsum_axis(Axis::Row),
+            Matrix::from(1, 4, vec![1, 3, 6, 10])
+        );
+
+        let mat = Matrix::from(2, 2, vec![1, 2, 3, 4]);
+        assert_eq!(
+            mat.cumsum_axis(Axis::Col),
+            Matrix::from(2, 2, vec![1, 2, 4, 6])
+        );
+    }
+
+    #[test]
+    fn scatter_rows_test() {
+        let mut grad = Matrix::<i32>::new(3, 2);
+        let source = Matrix::from(3, 2, vec![1, 2, 3, 4, 10, 20]);
+
+        grad.scatter_rows(&[2, 0, 2], &source);
+        assert_eq!(grad, Matrix::from(3, 2, vec![3, 4, 0, 0, 11, 22]));
+    }
+
+    #[test]
+    fn center_columns_test() {
+        let mat = Matrix::from(3, 2, vec![1., 10., 2., 20., 6., 60.]);
+
+        let (centered, means) = mat.center_columns();
+        assert_eq!(means, vec![3., 30.]);
+
+        let column_means = centered.mean_axis(Axis::Col);
+        for j in 0..2 {
+            assert!(column_means.at(0, j).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn covariance_test() {
+        let mat = Matrix::from(3, 2, vec![1, 2, 2, 4, 3, 5]);
+        let expected = [1., 1.5, 1.5, 7. / 3.];
+
+        let cov = mat.covariance();
+        assert_eq!((cov.rows, cov.cols), (2, 2));
+
+        for (actual, expected) in cov.data.iter().zip(expected.iter()) {
+            assert!((actual - expected).abs() < 1e-12);
+        }
     }
 }
@@ -0,0 +1,216 @@
+use crate::matrix::{Axis, Matrix};
+use crate::utils::cmp_nan_lowest;
+use std::cmp::Ordering;
+
+// Fraction of samples whose true class (the argmax of the target row) is
+// among the k highest scoring predicted classes
+
+pub fn topk_accuracy(predictions: &Matrix<f64>, targets: &Matrix<f64>, k: usize) -> f64 {
+    assert_eq!(predictions.shape(), targets.shape());
+
+    let truth = targets.argmax_axis(Axis::Row);
+    let hits = predictions
+        .topk_rows(k)
+        .iter()
+        .zip(truth.iter())
+        .filter(|(topk, class)| topk.contains(class))
+        .count();
+
+    hits as f64 / predictions.rows as f64
+}
+
+// Coefficient of determination 1 - SS_res / SS_tot over every element.
+// A constant target has SS_tot = 0, so the score is defined as 1 for a
+// perfect prediction and 0 otherwise
+
+pub fn r2_score(predictions: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+    assert_eq!(predictions.shape(), targets.shape());
+
+    let mean = targets.data.iter().sum::<f64>() / targets.data.len() as f64;
+    let ss_res: f64 = predictions
+        .data
+        .iter()
+        .zip(targets.data.iter())
+        .map(|(p, t)| (t - p).powi(2))
+        .sum();
+    let ss_tot: f64 = targets.data.iter().map(|t| (t - mean).powi(2)).sum();
+
+    if ss_tot == 0. {
+        return if ss_res == 0. { 1. } else { 0. };
+    }
+
+    1. - ss_res / ss_tot
+}
+
+// Mean absolute error over every element
+
+pub fn mae(predictions: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+    assert_eq!(predictions.shape(), targets.shape());
+
+    let total: f64 = predictions
+        .data
+        .iter()
+        .zip(targets.data.iter())
+        .map(|(p, t)| (p - t).abs())
+        .sum();
+
+    total / predictions.data.len() as f64
+}
+
+// Precision, recall and F1 treating `positive_class` as positive and every
+// other label as negative. Each ratio with an empty denominator (no
+// predicted positives, no actual positives, or both scores zero) is 0
+
+pub fn precision_recall_f1(
+    predictions: &[usize],
+    targets: &[usize],
+    positive_class: usize,
+) -> (f64, f64, f64) {
+    assert_eq!(predictions.len(), targets.len());
+
+    let (mut tp, mut fp, mut fn_) = (0, 0, 0);
+    for (&p, &t) in predictions.iter().zip(targets.iter()) {
+        match (p == positive_class, t == positive_class) {
+            (true, true) => tp += 1,
+            (true, false) => fp += 1,
+            (false, true) => fn_ += 1,
+            (false, false) => {}
+        }
+    }
+
+    let ratio = |num: f64, den: f64| if den == 0. { 0. } else { num / den };
+
+    let precision = ratio(tp as f64, (tp + fp) as f64);
+    let recall = ratio(tp as f64, (tp + fn_) as f64);
+    let f1 = ratio(2. * precision * recall, precision + recall);
+
+    (precision, recall, f1)
+}
+
+// Area under the ROC curve from the Mann-Whitney U statistic: the chance
+// that a random positive (label 1) outscores a random negative (label 0).
+// Tied scores share their average rank, counting as half a win. A NaN
+// score ranks below every number, and NaN scores tie with each other
+
+pub fn roc_auc(scores: &[f64], labels: &[usize]) -> f64 {
+    assert_eq!(scores.len(), labels.len());
+    assert!(labels.iter().all(|&label| label <= 1));
+
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| cmp_nan_lowest(&scores[a], &scores[b]));
+
+    // 1-based ranks, averaged across each run of equal scores
+    let mut ranks = vec![0.; scores.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len()
+            && cmp_nan_lowest(&scores[order[end]], &scores[order[start]]) == Ordering::Equal
+        {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+
+    let n_pos = labels.iter().filter(|&&label| label == 1).count() as f64;
+    let n_neg = labels.len() as f64 - n_pos;
+    assert!(
+        n_pos > 0. && n_neg > 0.,
+        "ROC-AUC needs both positive and negative samples.."
+    );
+
+    let rank_sum: f64 = ranks
+        .iter()
+        .zip(labels.iter())
+        .filter(|(_, &label)| label == 1)
+        .map(|(rank, _)| rank)
+        .sum();
+
+    (rank_sum - n_pos * (n_pos + 1.) / 2.) / (n_pos * n_neg)
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::{mae, precision_recall_f1, r2_score, roc_auc, topk_accuracy};
+    use crate::matrix::Matrix;
+    use crate::random::{Rng, SplitMix64};
+
+    #[test]
+    fn topk_accuracy_test() {
+        // The true class is the second highest prediction
+        let predictions = Matrix::from(1, 3, vec![0.5, 0.3, 0.2]);
+        let targets = Matrix::from(1, 3, vec![0., 1., 0.]);
+
+        assert_eq!(topk_accuracy(&predictions, &targets, 1), 0.);
+        assert_eq!(topk_accuracy(&predictions, &targets, 2), 1.);
+
+        let predictions = Matrix::from(2, 3, vec![0.5, 0.3, 0.2, 0.1, 0.2, 0.7]);
+        let targets = Matrix::from(2, 3, vec![0., 1., 0., 0., 0., 1.]);
+        assert_eq!(topk_accuracy(&predictions, &targets, 1), 0.5);
+    }
+
+    #[test]
+    fn regression_metrics_test() {
+        let targets = Matrix::from(4, 1, vec![1., 2., 3., 4.]);
+
+        assert_eq!(r2_score(&targets, &targets), 1.);
+        assert_eq!(mae(&targets, &targets), 0.);
+
+        let predictions = Matrix::from(4, 1, vec![1.5, 2., 2., 4.5]);
+        assert_eq!(mae(&predictions, &targets), 0.5);
+        assert!((r2_score(&predictions, &targets) - (1. - 1.5 / 5.)).abs() < 1e-12);
+
+        // Predicting the mean scores zero
+        let mean = Matrix::filled(4, 1, 2.5);
+        assert_eq!(r2_score(&mean, &targets), 0.);
+
+        let constant = Matrix::filled(4, 1, 3.);
+        assert_eq!(r2_score(&constant, &constant), 1.);
+        assert_eq!(r2_score(&targets, &constant), 0.);
+    }
+
+    #[test]
+    fn precision_recall_f1_test() {
+        // 2 true positives, 1 false positive, 2 false negatives
+        let predictions = [1, 1, 1, 0, 0, 0, 2];
+        let targets = [1, 1, 0, 1, 0, 0, 1];
+
+        let (precision, recall, f1) = precision_recall_f1(&predictions, &targets, 1);
+        assert!((precision - 2. / 3.).abs() < 1e-12);
+        assert!((recall - 0.5).abs() < 1e-12);
+        assert!((f1 - 4. / 7.).abs() < 1e-12);
+
+        // Class 2 is predicted once and never correct; class 3 never predicted
+        assert_eq!(precision_recall_f1(&predictions, &targets, 2), (0., 0., 0.));
+        assert_eq!(precision_recall_f1(&predictions, &targets, 3), (0., 0., 0.));
+    }
+
+    #[test]
+    fn roc_auc_test() {
+        let labels = [0, 0, 1, 1];
+        assert_eq!(roc_auc(&[0.1, 0.2, 0.8, 0.9], &labels), 1.);
+        assert_eq!(roc_auc(&[0.9, 0.8, 0.2, 0.1], &labels), 0.);
+
+        // Every score tied: each pair counts as half a win
+        assert_eq!(roc_auc(&[0.5; 4], &labels), 0.5);
+
+        // One positive tied with one negative, the other pairs ordered
+        assert_eq!(roc_auc(&[0.1, 0.5, 0.5, 0.9], &labels), 0.875);
+
+        // NaN scores rank lowest and tie with each other
+        assert_eq!(roc_auc(&[f64::NAN, 0.2, 0.8, 0.9], &labels), 1.);
+        assert_eq!(roc_auc(&[0.1, 0.2, f64::NAN, 0.9], &labels), 0.5);
+        assert_eq!(roc_auc(&[f64::NAN; 4], &labels), 0.5);
+
+        let mut rng = SplitMix64::new(5);
+        let scores: Vec<f64> = (0..2000).map(|_| rng.next_f64()).collect();
+        let labels: Vec<usize> = (0..2000).map(|_| rng.gen_index(2)).collect();
+        assert!((roc_auc(&scores, &labels) - 0.5).abs() < 0.05);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::linalg::pairwise_distances;
+use crate::loss::bce_with_logits;
+use crate::matrix::Matrix;
+use crate::random::{self, Rng};
+use crate::utils::cmp_nan_highest;
+
+// Ridge regression weights from the normal equations
+// (X^T·X + lambda·I)·w = X^T·y. Any positive lambda makes the system
+// solvable, even when the features are collinear
+
+pub fn ridge_fit(x: &Matrix<f64>, y: &Matrix<f64>, lambda: f64) -> Matrix<f64> {
+    assert_eq!(x.rows, y.rows);
+
+    let xt = x.transpose();
+    let lhs = (&xt * x).diag_add(lambda);
+    let rhs = &xt * y;
+
+    lhs.solve(&rhs)
+        .expect("Ridge system is singular, try a positive lambda..")
+}
+
+// Lloyd's algorithm on the rows of `data`. Centroids start at k distinct
+// random rows; iteration stops once no assignment changes or after
+// `max_iter` rounds. A cluster that loses all its points keeps its centroid
+
+pub fn kmeans<R: Rng>(
+    data: &Matrix<f64>,
+    k: usize,
+    max_iter: usize,
+    rng: &mut R,
+) -> (Matrix<f64>, Vec<usize>) {
+    assert!(k > 0 && k <= data.rows);
+
+    let mut order: Vec<usize> = (0..data.rows).collect();
+    random::shuffle(&mut order, rng);
+
+    let mut centroids = Matrix::<f64>::new(k, data.cols);
+    for (c, &row) in order.iter().take(k).enumerate() {
+        for j in 0..data.cols {
+            centroids.set(c, j, data.at(row, j));
+        }
+    }
+
+    let mut assignments = vec![0; data.rows];
+
+    for iter in 0..max_iter {
+        let mut changed = false;
+        let distances = pairwise_distances(data, &centroids);
+        for (i, assignment) in assignments.iter_mut().enumerate() {
+            // A NaN distance never wins, and equal distances keep the
+            // lowest centroid index
+            let nearest = (0..k)
+                .min_by(|&a, &b| cmp_nan_highest(&distances.at(i, a), &distances.at(i, b)))
+                .unwrap();
+
+            if nearest != *assignment {
+                *assignment = nearest;
+                changed = true;
+            }
+        }
+
+        if iter > 0 && !changed {
+            break;
+        }
+
+        let mut sums = Matrix::<f64>::new(k, data.cols);
+        let mut counts = vec![0usize; k];
+        for (i, &c) in assignments.iter().enumerate() {
+            counts[c] += 1;
+            for j in 0..data.cols {
+                sums.set(c, j, sums.at(c, j) + data.at(i, j));
+            }
+        }
+
+        for (c, &count) in counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            for j in 0..data.cols {
+                centroids.set(c, j, sums.at(c, j) / count as f64);
+            }
+        }
+    }
+
+    (centroids, assignments)
+}
+
+// Classifies each row by majority vote among its k nearest training rows.
+// Tied votes go to the smallest label
+
+#[derive(Clone, Debug)]
+pub struct KnnClassifier {
+    k: usize,
+    x: Matrix<f64>,
+    y: Vec<usize>,
+}
+
+impl KnnClassifier {
+    pub fn new(k: usize) -> Self {
+        assert!(k > 0);
+
+        Self {
+            k,
+            x: Matrix::new(0, 0),
+            y: Vec::new(),
+        }
+    }
+
+    pub fn fit(&mut self, x: &Matrix<f64>, y: &[usize]) {
+        assert_eq!(x.rows, y.len());
+        assert!(self.k <= x.rows);
+
+        self.x = x.clone();
+        self.y = y.to_vec();
+    }
+
+    pub fn predict(&self, x: &Matrix<f64>) -> Vec<usize> {
+        assert!(
+            !self.y.is_empty(),
+            "KnnClassifier::predict called before fit.."
+        );
+
+        let distances = pairwise_distances(x, &self.x);
+        let n_labels = self.y.iter().max().unwrap() + 1;
+
+        (0..x.rows)
+            .map(|i| {
+                let mut order: Vec<usize> = (0..self.x.rows).collect();
+                // Training rows at a NaN distance rank after every other
+                order.sort_by(|&a, &b| cmp_nan_highest(&distances.at(i, a), &distances.at(i, b)));
+
+                let mut votes = vec![0usize; n_labels];
+                for &neighbour in order.iter().take(self.k) {
+                    votes[self.y[neighbour]] += 1;
+                }
+
+                // max_by_key keeps the last maximum, so scan labels in reverse
+                (0..n_labels)
+                    .rev()
+                    .max_by_key(|&label| votes[label])
+                    .unwrap()
+            })
+            .collect()
+    }
+}
+
+// Binary logistic regression on row samples, trained by full-batch
+// gradient descent on the cross-entropy of the logits. Labels are 0 or 1
+
+#[derive(Clone, Debug)]
+pub struct LogisticRegression {
+    weights: Matrix<f64>,
+    bias: f64,
+}
+
+impl LogisticRegression {
+    pub fn new(n_features: usize) -> Self {
+        Self {
+            weights: Matrix::new(n_features, 1),
+            bias: 0.,
+        }
+    }
+
+    pub fn weights(&self) -> &Matrix<f64> {
+        &self.weights
+    }
+
+    pub fn bias(&self) -> f64 {
+        self.bias
+    }
+
+    fn logits(&self, x: &Matrix<f64>) -> Matrix<f64> {
+        (x * &self.weights).map(|val| val + self.bias)
+    }
+
+    // Returns the loss before each epoch's update
+    pub fn fit(&mut self, x: &Matrix<f64>, y: &Matrix<f64>, lr: f64, epochs: usize) -> Vec<f64> {
+        assert_eq!(x.cols, self.weights.rows);
+        assert_eq!(y.shape(), (x.rows, 1));
+
+        let xt = x.transpose();
+        let mut losses = Vec::with_capacity(epochs);
+
+        for _ in 0..epochs {
+            let (loss, grad) = bce_with_logits(&self.logits(x), y);
+            losses.push(loss);
+
+            let grad_weights = &xt * &grad;
+            self.weights = self
+                .weights
+                .map_with_by_ref(&grad_weights, |w, g| w - lr * g);
+            self.bias -= lr * grad.data.iter().sum::<f64>();
+        }
+
+        losses
+    }
+
+    pub fn predict_proba(&self, x: &Matrix<f64>) -> Matrix<f64> {
+        self.logits(x).sigmoid()
+    }
+
+    pub fn predict(&self, x: &Matrix<f64>) -> Vec<usize> {
+        self.predict_proba(x)
+            .data
+            .iter()
+            .map(|&p| if p >= 0.5 { 1 } else { 0 })
+            .collect()
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::{kmeans, ridge_fit, KnnClassifier, LogisticRegression};
+    use crate::matrix::Matrix;
+    use crate::random::SplitMix64;
+
+    #[test]
+    fn ridge_fit_test() {
+        // y = 2·x0 - 3·x1
+        let x = Matrix::from(4, 2, vec![1., 0., 0., 1., 1., 1., 2., -1.]);
+        let y = Matrix::from(4, 1, vec![2., -3., -1., 7.]);
+
+        let weights = ridge_fit(&x, &y, 1e-8);
+        assert!((weights.at(0, 0) - 2.).abs() < 1e-6);
+        assert!((weights.at(1, 0) + 3.).abs() < 1e-6);
+
+        let shrunk = ridge_fit(&x, &y, 1e6);
+        assert!(shrunk.norm() < 1e-3 * weights.norm());
+    }
+
+    #[test]
+    fn kmeans_test() {
+        let data = Matrix::from(
+            6,
+            2,
+            vec![0., 0., 0.2, -0.1, -0.1, 0.3, 10., 10., 10.2, 9.9, 9.8, 10.1],
+        );
+
+        for seed in 0..5 {
+            let (centroids, assignments) = kmeans(&data, 2, 100, &mut SplitMix64::new(seed));
+
+            assert!(assignments[..3].iter().all(|&c| c == assignments[0]));
+            assert!(assignments[3..].iter().all(|&c| c == assignments[3]));
+            assert_ne!(assignments[0], assignments[3]);
+
+            let far = assignments[3];
+            assert!((centroids.at(far, 0) - 10.).abs() < 1e-9);
+            assert!((centroids.at(far, 1) - 10.).abs() < 1e-9);
+        }
+
+        // A NaN row gives the same clustering on every run with the seed
+        let mut data = data;
+        data.set(1, 0, f64::NAN);
+        let (_, first) = kmeans(&data, 2, 100, &mut SplitMix64::new(3));
+        let (_, second) = kmeans(&data, 2, 100, &mut SplitMix64::new(3));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn knn_classifier_test() {
+        let x = Matrix::from(6, 2, vec![0., 0., 1., 0., 0., 1., 5., 5., 6., 5., 5., 6.]);
+        let y = [0, 0, 0, 1, 1, 1];
+
+        let mut knn = KnnClassifier::new(3);
+        knn.fit(&x, &y);
+
+        let held_out = Matrix::from(3, 2, vec![0.5, 0.5, 5.5, 5.2, 4., 4.5]);
+        assert_eq!(knn.predict(&held_out), vec![0, 1, 1]);
+
+        // Every distance from a NaN query ties, so the first k rows vote
+        let nan_query = Matrix::from(1, 2, vec![f64::NAN, 0.]);
+        assert_eq!(knn.predict(&nan_query), vec![0]);
+
+        // NaN training rows are never among the nearest neighbours
+        let mut x = x;
+        x.set(0, 0, f64::NAN);
+        x.set(1, 1, f64::NAN);
+        knn.fit(&x, &y);
+        assert_eq!(knn.predict(&Matrix::from(1, 2, vec![6., 5.])), vec![1]);
+    }
+
+    #[test]
+    fn logistic_regression_test() {
+        // Class 1 whenever x0 + x1 > 1
+        let mut rng = SplitMix64::new(11);
+        let x = Matrix::random_uniform(100, 2, -1., 2., &mut rng);
+        let labels: Vec<usize> = (0..x.rows)
+            .map(|i| (x.at(i, 0) + x.at(i, 1) > 1.) as usize)
+            .collect();
+        let y = Matrix::from(x.rows, 1, labels.iter().map(|&l| l as f64).collect());
+
+        let mut model = LogisticRegression::new(2);
+        let losses = model.fit(&x, &y, 1., 500);
+        assert!(losses.last().unwrap() < &losses[0]);
+
+        let correct = model
+            .predict(&x)
+            .iter()
+            .zip(labels.iter())
+            .filter(|(a, b)| a == b)
+            .count();
+        assert!(correct >= 95);
+
+        let proba = model.predict_proba(&x);
+        assert!(proba.data.iter().all(|p| (0. ..=1.).contains(p)));
+    }
+}
@@ -0,0 +1,182 @@
+use crate::layers::Layer;
+use crate::loss;
+use crate::matrix::Matrix;
+use num::Float;
+
+// A stack of layers applied in order
+
+pub struct Network<T = f64> {
+    layers: Vec<Box<dyn Layer<T>>>,
+}
+
+impl<T: Float> Default for Network<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Network<T> {
+    pub fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    pub fn add<L: Layer<T> + 'static>(&mut self, layer: L) {
+        self.layers.push(Box::new(layer));
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    pub fn forward(&mut self, input: &Matrix<T>) -> Matrix<T> {
+        let mut output = input.clone();
+        for layer in self.layers.iter_mut() {
+            output = layer.forward(&output);
+        }
+
+        output
+    }
+
+    pub fn backward(&mut self, grad_output: &Matrix<T>) -> Matrix<T> {
+        let mut grad = grad_output.clone();
+        for layer in self.layers.iter_mut().rev() {
+            grad = layer.backward(&grad);
+        }
+
+        grad
+    }
+
+    pub fn zero_grad(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.zero_grad();
+        }
+    }
+
+    pub fn step(&mut self, learning_rate: T) {
+        for layer in self.layers.iter_mut() {
+            layer.step(learning_rate);
+        }
+    }
+
+    // Full-batch gradient descent on the mean squared error. The callback,
+    // if given, runs after every epoch with the epoch index and its loss.
+    // Returns the loss recorded for each epoch
+    pub fn fit(
+        &mut self,
+        x: &Matrix<T>,
+        y: &Matrix<T>,
+        epochs: usize,
+        learning_rate: T,
+        mut callback: Option<&mut dyn FnMut(usize, T)>,
+    ) -> Vec<T> {
+        let mut history = Vec::with_capacity(epochs);
+
+        for epoch in 0..epochs {
+            self.zero_grad();
+
+            let output = self.forward(x);
+            let loss = loss::mse(&output, y);
+
+            self.backward(&loss::mse_grad(&output, y));
+            self.step(learning_rate);
+
+            if let Some(callback) = callback.as_mut() {
+                callback(epoch, loss);
+            }
+            history.push(loss);
+        }
+
+        history
+    }
+}
+
+// Macros
+
+// Builds a network from layers listed in order, as in
+// network![Dense::new(2, 4), Relu::new(), Dense::new(4, 1)]
+
+#[macro_export]
+macro_rules! network {
+    ( $( $layer:expr ),* $(,)? ) => {
+        {
+            #[allow(unused_mut)]
+            let mut network = $crate::network::Network::new();
+            $( network.add($layer); )*
+
+            network
+        }
+    };
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::Network;
+    use crate::layers::{Dense, Relu};
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn fit_callback_test() {
+        // y = x1 - 2·x2
+        let x = Matrix::from(4, 2, vec![0., 0., 1., 0., 0., 1., 1., 1.]);
+        let y = Matrix::from(4, 1, vec![0., 1., -2., -1.]);
+
+        let mut network = Network::new();
+        network.add(Dense::new(2, 1));
+
+        let mut calls = Vec::new();
+        let history = network.fit(
+            &x,
+            &y,
+            50,
+            0.1,
+            Some(&mut |epoch, loss| calls.push((epoch, loss))),
+        );
+
+        assert_eq!(calls.len(), 50);
+        assert!(calls.iter().enumerate().all(|(i, &(epoch, _))| epoch == i));
+        assert_eq!(
+            calls.iter().map(|&(_, loss)| loss).collect::<Vec<_>>(),
+            history
+        );
+        assert!(history[49] < history[0]);
+
+        // Training without a callback still works
+        let history = network.fit(&x, &y, 5, 0.1, None);
+        assert_eq!(history.len(), 5);
+    }
+
+    #[test]
+    fn f32_network_test() {
+        let x = Matrix::<f32>::from(4, 2, vec![0., 0., 1., 0., 0., 1., 1., 1.]);
+        let y = Matrix::<f32>::from(4, 1, vec![0., 1., -2., -1.]);
+
+        let mut network = Network::<f32>::new();
+        network.add(Dense::new(2, 3));
+        network.add(Dense::new(3, 1));
+
+        let history = network.fit(&x, &y, 20, 0.05, None);
+        assert!(history.iter().all(|loss| loss.is_finite()));
+
+        let output = network.forward(&x);
+        assert_eq!(output.shape(), (4, 1));
+        assert!(output.data.iter().all(|val| val.is_finite()));
+    }
+
+    #[test]
+    fn network_macro_test() {
+        let mut network = network![Dense::new(2, 4), Relu::new(), Dense::new(4, 1)];
+        assert_eq!(network.len(), 3);
+
+        let output = network.forward(&Matrix::from(3, 2, vec![1., 2., -1., 0., 0.5, 0.5]));
+        assert_eq!(output.shape(), (3, 1));
+
+        let empty: Network = network![];
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::matrix::Matrix;
+use num::traits::Num;
+
+// Recycles the buffers of temporary matrices. `take` hands out a zeroed
+// matrix, reusing a returned buffer when one is free, so a loop that
+// recycles its temporaries stops allocating once the pool is warm
+
+const MAX_POOLED: usize = 16;
+
+#[derive(Clone, Debug, Default)]
+pub struct MatrixPool<T> {
+    free: Vec<Vec<T>>,
+}
+
+impl<T: Clone + Num> MatrixPool<T> {
+    pub fn new() -> Self {
+        Self { free: Vec::new() }
+    }
+
+    // Buffers currently waiting to be reused
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+
+    pub fn take(&mut self, rows: usize, cols: usize) -> Matrix<T> {
+        let len = rows * cols;
+
+        // Prefer a buffer that is already big enough
+        let mut data = match self.free.iter().position(|buf| buf.capacity() >= len) {
+            Some(index) => self.free.swap_remove(index),
+            None => self.free.pop().unwrap_or_default(),
+        };
+        data.clear();
+        data.resize(len, num::zero());
+
+        Matrix::from(rows, cols, data)
+    }
+
+    // Beyond MAX_POOLED buffers the matrix is simply dropped
+    pub fn recycle(&mut self, mat: Matrix<T>) {
+        if self.free.len() < MAX_POOLED {
+            self.free.push(mat.data);
+        }
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::MatrixPool;
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn pool_reuse_test() {
+        let mut pool = MatrixPool::<f64>::new();
+
+        let mut mat = pool.take(3, 4);
+        assert_eq!(mat, Matrix::new(3, 4));
+        mat.fill(5.);
+
+        let buffer = mat.data.as_ptr();
+        pool.recycle(mat);
+        assert_eq!(pool.len(), 1);
+
+        // Same allocation comes back zeroed, even for a smaller shape
+        let reused = pool.take(2, 2);
+        assert_eq!(reused.data.as_ptr(), buffer);
+        assert_eq!(reused, Matrix::new(2, 2));
+        assert!(pool.is_empty());
+    }
+}
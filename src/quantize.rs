@@ -0,0 +1,71 @@
+use crate::matrix::Matrix;
+
+// Affine (asymmetric) int8 quantization: x ≈ scale · (q - zero_point).
+// The range is widened to include zero so that zero is represented exactly,
+// and every in-range value is recovered to within scale / 2
+
+impl Matrix<f32> {
+    pub fn quantize_int8(&self) -> (Matrix<i8>, f32, i32) {
+        let min = self.data.iter().cloned().fold(0f32, f32::min);
+        let max = self.data.iter().cloned().fold(0f32, f32::max);
+
+        // An all-zero matrix has no range; any positive scale will do
+        let scale = if max > min { (max - min) / 255. } else { 1. };
+        let zero_point = (-128. - min / scale).round().clamp(-128., 127.) as i32;
+
+        let data = self
+            .data
+            .iter()
+            .map(|&val| {
+                let q = (val / scale).round() as i32 + zero_point;
+                q.clamp(-128, 127) as i8
+            })
+            .collect();
+
+        (Matrix::from(self.rows, self.cols, data), scale, zero_point)
+    }
+}
+
+pub fn dequantize_int8(mat: &Matrix<i8>, scale: f32, zero_point: i32) -> Matrix<f32> {
+    let data = mat
+        .data
+        .iter()
+        .map(|&q| scale * (i32::from(q) - zero_point) as f32)
+        .collect();
+
+    Matrix::from(mat.rows, mat.cols, data)
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::dequantize_int8;
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn int8_round_trip_test() {
+        let mat = Matrix::<f32>::from(2, 3, vec![-1.5, 0., 0.3, 2.75, -0.01, 1.]);
+
+        let (quantized, scale, zero_point) = mat.quantize_int8();
+        assert!((scale - 4.25 / 255.).abs() < 1e-7);
+
+        let restored = dequantize_int8(&quantized, scale, zero_point);
+        for (a, b) in restored.data.iter().zip(mat.data.iter()) {
+            assert!((a - b).abs() <= scale / 2. + 1e-6);
+        }
+        assert_eq!(restored.at(0, 1), 0.);
+
+        // All-positive input still maps zero exactly, and the extremes saturate
+        let positive = Matrix::<f32>::from(1, 2, vec![1., 4.]);
+        let (quantized, scale, zero_point) = positive.quantize_int8();
+        assert_eq!(zero_point, -128);
+        assert_eq!(quantized.at(0, 1), 127);
+        let restored = dequantize_int8(&quantized, scale, zero_point);
+        assert!((restored.at(0, 1) - 4.).abs() <= scale / 2.);
+
+        let zeros = Matrix::<f32>::new(2, 2);
+        let (quantized, scale, zero_point) = zeros.quantize_int8();
+        assert_eq!(dequantize_int8(&quantized, scale, zero_point), zeros);
+    }
+}
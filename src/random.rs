@@ -0,0 +1,126 @@
+use crate::matrix::Matrix;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// Seed handed to generators that are created without an explicit seed.
+// Advanced on every use, so each `default_rng()` draws a distinct stream.
+// The streams only repeat from run to run when generators are created in
+// the same order on one thread; use `seeded_rng` for reproducible runs
+static NEXT_SEED: AtomicU64 = AtomicU64::new(0x5eed);
+
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    // Uniform in [0, 1), built from the top 53 bits
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn gen_range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    // Uniform in [0, n). The modulo bias is negligible for any n that fits
+    // in memory
+    fn gen_index(&mut self, n: usize) -> usize {
+        assert!(n > 0);
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// SplitMix64, small and fast with good statistical quality for init and
+// shuffling. Not suitable for anything cryptographic
+
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl Rng for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+pub fn seeded_rng(seed: u64) -> impl Rng {
+    SplitMix64::new(seed)
+}
+
+pub(crate) fn default_rng() -> SplitMix64 {
+    SplitMix64::new(NEXT_SEED.fetch_add(GOLDEN_GAMMA, Ordering::Relaxed))
+}
+
+// Fisher-Yates shuffle
+pub fn shuffle<T, R: Rng>(items: &mut [T], rng: &mut R) {
+    for i in (1..items.len()).rev() {
+        let j = rng.gen_index(i + 1);
+        items.swap(i, j);
+    }
+}
+
+impl Matrix<f64> {
+    pub fn random_uniform<R: Rng>(
+        rows: usize,
+        cols: usize,
+        low: f64,
+        high: f64,
+        rng: &mut R,
+    ) -> Self {
+        let data = (0..rows * cols).map(|_| rng.gen_range(low, high)).collect();
+
+        Matrix::from(rows, cols, data)
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::{seeded_rng, shuffle, Rng, SplitMix64};
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn range_test() {
+        let mut rng = SplitMix64::new(42);
+
+        for _ in 0..1000 {
+            let val = rng.gen_range(-2., 3.);
+            assert!((-2. ..3.).contains(&val));
+        }
+    }
+
+    #[test]
+    fn seeded_rng_test() {
+        let mat1 = Matrix::random_uniform(3, 4, -1., 1., &mut seeded_rng(2024));
+        let mat2 = Matrix::random_uniform(3, 4, -1., 1., &mut seeded_rng(2024));
+        let mat3 = Matrix::random_uniform(3, 4, -1., 1., &mut seeded_rng(2025));
+
+        assert_eq!(mat1, mat2);
+        assert!(mat1 != mat3);
+    }
+
+    #[test]
+    fn shuffle_test() {
+        let mut items: Vec<usize> = (0..20).collect();
+        shuffle(&mut items, &mut seeded_rng(1));
+
+        let mut again: Vec<usize> = (0..20).collect();
+        shuffle(&mut again, &mut seeded_rng(1));
+        assert_eq!(items, again);
+
+        again.sort();
+        assert_eq!(again, (0..20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::matrix::Matrix;
+use std::io::{self, Read, Write};
+
+// Binary layout: the 4-byte magic, a version byte, a dtype tag byte, then
+// rows and cols as little-endian u64, followed by every element in
+// row-major order as little-endian bytes
+
+pub(crate) const MAGIC: &[u8; 4] = b"RNMX";
+pub(crate) const VERSION: u8 = 1;
+pub(crate) const HEADER_LEN: usize = 22;
+
+// Element types with a fixed-width little-endian encoding
+
+pub trait BinElement: Sized {
+    const TAG: u8;
+    const SIZE: usize;
+
+    fn write_le<W: Write>(&self, w: &mut W) -> io::Result<()>;
+
+    fn from_le(bytes: &[u8]) -> Self;
+}
+
+impl BinElement for f32 {
+    const TAG: u8 = 1;
+    const SIZE: usize = 4;
+
+    fn write_le<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.to_le_bytes())
+    }
+
+    fn from_le(bytes: &[u8]) -> Self {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(bytes);
+        f32::from_le_bytes(buf)
+    }
+}
+
+impl BinElement for f64 {
+    const TAG: u8 = 2;
+    const SIZE: usize = 8;
+
+    fn write_le<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.to_le_bytes())
+    }
+
+    fn from_le(bytes: &[u8]) -> Self {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(bytes);
+        f64::from_le_bytes(buf)
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Checks the magic, version and dtype tag and returns (rows, cols)
+pub(crate) fn parse_header<T: BinElement>(header: &[u8]) -> io::Result<(usize, usize)> {
+    if header.len() < HEADER_LEN || &header[..4] != MAGIC {
+        return Err(invalid("not a matrix file"));
+    }
+    if header[4] != VERSION {
+        return Err(invalid("unsupported matrix file version"));
+    }
+    if header[5] != T::TAG {
+        return Err(invalid("matrix file holds a different element type"));
+    }
+
+    let dimension = |bytes: &[u8]| -> io::Result<usize> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(bytes);
+        let val = u64::from_le_bytes(buf);
+        if val > usize::MAX as u64 {
+            return Err(invalid("matrix dimension too large"));
+        }
+        Ok(val as usize)
+    };
+
+    Ok((dimension(&header[6..14])?, dimension(&header[14..22])?))
+}
+
+impl<T: Clone + BinElement> Matrix<T> {
+    pub fn write_bin<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION, T::TAG])?;
+        w.write_all(&(self.rows as u64).to_le_bytes())?;
+        w.write_all(&(self.cols as u64).to_le_bytes())?;
+
+        for val in self.data.iter() {
+            val.write_le(w)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_bin<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut header = [0; HEADER_LEN];
+        r.read_exact(&mut header)?;
+        let (rows, cols) = parse_header::<T>(&header)?;
+
+        let len = rows
+            .checked_mul(cols)
+            .ok_or_else(|| invalid("matrix dimensions overflow"))?;
+
+        // Read element by element rather than trusting the header with one
+        // large up-front allocation
+        let mut data = Vec::new();
+        let mut buf = vec![0; T::SIZE];
+        for _ in 0..len {
+            r.read_exact(&mut buf)?;
+            data.push(T::from_le(&buf));
+        }
+
+        Ok(Self::from(rows, cols, data))
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::HEADER_LEN;
+    use crate::matrix::Matrix;
+    use std::io::{Cursor, ErrorKind};
+
+    #[test]
+    fn round_trip_test() {
+        let mat = Matrix::<f64>::from(2, 3, vec![1.5, -0., f64::MAX, 1e-300, -7.25, 3.]);
+        let mut buffer = Vec::new();
+        mat.write_bin(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), HEADER_LEN + 6 * 8);
+
+        let read = Matrix::<f64>::read_bin(&mut Cursor::new(&buffer)).unwrap();
+        assert_eq!(read.shape(), (2, 3));
+        assert_eq!(read.data, mat.data);
+
+        let mat = Matrix::<f32>::from(3, 1, vec![0.1, f32::MIN, 2.]);
+        let mut buffer = Vec::new();
+        mat.write_bin(&mut buffer).unwrap();
+
+        let read = Matrix::<f32>::read_bin(&mut Cursor::new(&buffer)).unwrap();
+        assert_eq!(read, mat);
+    }
+
+    #[test]
+    fn invalid_file_test() {
+        let mut buffer = Vec::new();
+        Matrix::<f32>::from(1, 2, vec![1., 2.])
+            .write_bin(&mut buffer)
+            .unwrap();
+
+        // Wrong element type
+        let err = Matrix::<f64>::read_bin(&mut Cursor::new(&buffer)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // Truncated data
+        let err =
+            Matrix::<f32>::read_bin(&mut Cursor::new(&buffer[..buffer.len() - 1])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let err = Matrix::<f32>::read_bin(&mut Cursor::new(&b"not a matrix file at all"[..]))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
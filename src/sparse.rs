@@ -0,0 +1,180 @@
+use crate::error::ShapeError;
+use crate::matrix::Matrix;
+use num::{traits::Num, zero};
+use std::ops;
+
+// Compressed sparse row storage. The non-zeros of row i are
+// values[row_ptr[i]..row_ptr[i + 1]], with their columns in col_indices
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMatrix<T> {
+    rows: usize,
+    cols: usize,
+    row_ptr: Vec<usize>,
+    col_indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T: Clone + Num> SparseMatrix<T> {
+    // Entries sharing a position are summed, and explicit zeros dropped
+    pub fn from_triplets(rows: usize, cols: usize, triplets: &[(usize, usize, T)]) -> Self {
+        let mut sorted = triplets.to_vec();
+        sorted.sort_by_key(|&(i, j, _)| (i, j));
+
+        let mut merged: Vec<(usize, usize, T)> = Vec::with_capacity(sorted.len());
+        for (i, j, val) in sorted {
+            assert!(i < rows);
+            assert!(j < cols);
+
+            match merged.last_mut() {
+                Some(last) if last.0 == i && last.1 == j => {
+                    last.2 = last.2.clone() + val;
+                }
+                _ => merged.push((i, j, val)),
+            }
+        }
+
+        let mut row_ptr = vec![0; rows + 1];
+        let mut col_indices = Vec::with_capacity(merged.len());
+        let mut values = Vec::with_capacity(merged.len());
+
+        for (i, j, val) in merged {
+            if val.is_zero() {
+                continue;
+            }
+            row_ptr[i + 1] += 1;
+            col_indices.push(j);
+            values.push(val);
+        }
+
+        for i in 0..rows {
+            row_ptr[i + 1] += row_ptr[i];
+        }
+
+        Self {
+            rows,
+            cols,
+            row_ptr,
+            col_indices,
+            values,
+        }
+    }
+
+    pub fn from_dense(mat: &Matrix<T>) -> Self {
+        let triplets: Vec<_> = mat
+            .iter_indexed()
+            .filter(|(_, _, val)| !val.is_zero())
+            .map(|(i, j, val)| (i, j, val.clone()))
+            .collect();
+
+        Self::from_triplets(mat.rows, mat.cols, &triplets)
+    }
+
+    pub fn to_dense(&self) -> Matrix<T> {
+        let mut result = Matrix::<T>::new(self.rows, self.cols);
+
+        for i in 0..self.rows {
+            for k in self.row_ptr[i]..self.row_ptr[i + 1] {
+                result.set(i, self.col_indices[k], self.values[k].clone());
+            }
+        }
+
+        result
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> T {
+        assert!(i < self.rows);
+        assert!(j < self.cols);
+
+        let start = self.row_ptr[i];
+        let end = self.row_ptr[i + 1];
+
+        match self.col_indices[start..end].binary_search(&j) {
+            Ok(k) => self.values[start + k].clone(),
+            Err(_) => zero(),
+        }
+    }
+}
+
+// Sparse-dense multiplication, only touching the stored non-zeros
+
+impl<'b, T: Clone + Num> ops::Mul<&'b Matrix<T>> for &SparseMatrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, other: &'b Matrix<T>) -> Matrix<T> {
+        assert!(
+            self.cols == other.rows,
+            "{}",
+            ShapeError::Mismatch {
+                op: "sparse mul",
+                left: self.shape(),
+                right: other.shape(),
+            }
+        );
+
+        let mut result = Matrix::<T>::new(self.rows, other.cols);
+
+        for i in 0..self.rows {
+            for k in self.row_ptr[i]..self.row_ptr[i + 1] {
+                let col = self.col_indices[k];
+                for j in 0..other.cols {
+                    let val = result.at(i, j) + self.values[k].clone() * other.at(col, j);
+                    result.set(i, j, val);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::SparseMatrix;
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn from_triplets_test() {
+        let sparse = SparseMatrix::from_triplets(
+            3,
+            4,
+            &[(2, 1, 5), (0, 3, 1), (2, 1, 2), (1, 0, 0), (0, 0, 7)],
+        );
+
+        assert_eq!(sparse.nnz(), 3);
+        assert_eq!(sparse.get(0, 0), 7);
+        assert_eq!(sparse.get(0, 3), 1);
+        assert_eq!(sparse.get(2, 1), 7);
+        assert_eq!(sparse.get(1, 0), 0);
+        assert_eq!(sparse.get(1, 2), 0);
+
+        let dense = sparse.to_dense();
+        assert_eq!(
+            dense,
+            Matrix::from(3, 4, vec![7, 0, 0, 1, 0, 0, 0, 0, 0, 7, 0, 0])
+        );
+        assert_eq!(SparseMatrix::from_dense(&dense), sparse);
+    }
+
+    #[test]
+    fn sparse_mul_test() {
+        let identity = SparseMatrix::from_triplets(3, 3, &[(0, 0, 1.), (1, 1, 1.), (2, 2, 1.)]);
+        let dense = Matrix::from(3, 2, vec![1., 2., 3., 4., 5., 6.]);
+
+        assert_eq!(&identity * &dense, dense);
+
+        let sparse = SparseMatrix::from_dense(&Matrix::from(2, 3, vec![0., 2., 0., 1., 0., 3.]));
+        let expected = &sparse.to_dense() * &dense;
+        assert_eq!(&sparse * &dense, expected);
+    }
+}
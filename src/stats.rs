@@ -0,0 +1,80 @@
+use crate::matrix::Matrix;
+
+// Per-column running mean and population variance, using Welford's
+// algorithm so batches can be fed in one at a time
+
+#[derive(Clone, Debug)]
+pub struct RunningStats {
+    count: usize,
+    mean: Vec<f64>,
+    m2: Vec<f64>,
+}
+
+impl RunningStats {
+    pub fn new(cols: usize) -> Self {
+        Self {
+            count: 0,
+            mean: vec![0.; cols],
+            m2: vec![0.; cols],
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn update(&mut self, batch: &Matrix<f64>) {
+        assert_eq!(batch.cols, self.mean.len());
+
+        for i in 0..batch.rows {
+            self.count += 1;
+            let n = self.count as f64;
+
+            for (j, (mean, m2)) in self.mean.iter_mut().zip(self.m2.iter_mut()).enumerate() {
+                let val = batch.at(i, j);
+                let delta = val - *mean;
+                *mean += delta / n;
+                *m2 += delta * (val - *mean);
+            }
+        }
+    }
+
+    pub fn mean(&self) -> Matrix<f64> {
+        Matrix::from(1, self.mean.len(), self.mean.clone())
+    }
+
+    pub fn variance(&self) -> Matrix<f64> {
+        let n = self.count.max(1) as f64;
+        let data = self.m2.iter().map(|m2| m2 / n).collect();
+
+        Matrix::from(1, self.m2.len(), data)
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::RunningStats;
+    use crate::matrix::{Axis, Matrix};
+
+    #[test]
+    fn running_stats_test() {
+        let first = Matrix::from(2, 2, vec![1., 10., 4., 20.]);
+        let second = Matrix::from(3, 2, vec![2., 30., 8., 40., 5., 50.]);
+        let all = Matrix::from(5, 2, vec![1., 10., 4., 20., 2., 30., 8., 40., 5., 50.]);
+
+        let mut stats = RunningStats::new(2);
+        stats.update(&first);
+        stats.update(&second);
+        assert_eq!(stats.count(), 5);
+
+        let (centered, means) = all.center_columns();
+        let expected_var = centered.hadamard(&centered).mean_axis(Axis::Col);
+
+        for (j, mean) in means.iter().enumerate() {
+            assert!((stats.mean().at(0, j) - mean).abs() < 1e-12);
+            assert!((stats.variance().at(0, j) - expected_var.at(0, j)).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Stops training once `patience` epochs pass without the validation loss
+// improving on the best seen by more than `min_delta`
+
+#[derive(Clone, Debug)]
+pub struct EarlyStopping {
+    pub patience: usize,
+    pub min_delta: f64,
+    best: f64,
+    wait: usize,
+}
+
+impl EarlyStopping {
+    pub fn new(patience: usize, min_delta: f64) -> Self {
+        Self {
+            patience,
+            min_delta,
+            best: f64::INFINITY,
+            wait: 0,
+        }
+    }
+
+    pub fn best(&self) -> f64 {
+        self.best
+    }
+
+    pub fn should_stop(&mut self, val_loss: f64) -> bool {
+        if val_loss < self.best - self.min_delta {
+            self.best = val_loss;
+            self.wait = 0;
+            return false;
+        }
+
+        self.wait += 1;
+        self.wait >= self.patience
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use super::EarlyStopping;
+
+    #[test]
+    fn early_stopping_test() {
+        let losses = [1.0, 0.8, 0.79, 0.795, 0.78, 0.5];
+        let mut stopper = EarlyStopping::new(2, 0.05);
+
+        let stopped_at = losses.iter().position(|&loss| stopper.should_stop(loss));
+
+        // 0.79 and 0.795 both fail to beat 0.8 by the margin
+        assert_eq!(stopped_at, Some(3));
+        assert_eq!(stopper.best(), 0.8);
+    }
+}
@@ -1,3 +1,9 @@
+use num::Float;
+use std::cmp::Ordering;
+use std::thread;
+
+const PAIRWISE_BLOCK: usize = 64;
+
 pub fn get_integral_square_root(val: usize) -> Option<usize> {
     let square_root = (val as f64).sqrt();
 
@@ -7,3 +13,61 @@ pub fn get_integral_square_root(val: usize) -> Option<usize> {
 
     Some(square_root as usize)
 }
+
+// Converts an f64 constant into any float element type
+pub(crate) fn float<T: Float>(val: f64) -> T {
+    T::from(val).expect("Constant is not representable in this float type..")
+}
+
+// Logistic function, split on sign so neither branch can overflow
+pub(crate) fn sigmoid<T: Float>(x: T) -> T {
+    if x >= T::zero() {
+        T::one() / (T::one() + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (T::one() + e)
+    }
+}
+
+// Total order for sorting partially ordered values. NaN, the one value not
+// comparable to itself, ranks below everything else and equal to itself
+pub(crate) fn cmp_nan_lowest<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    match (a.partial_cmp(a).is_none(), b.partial_cmp(b).is_none()) {
+        (false, false) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (a_nan, b_nan) => b_nan.cmp(&a_nan),
+    }
+}
+
+// Total order on f64 that puts NaN, whatever its sign bit, after every
+// number. Suits distances, where a NaN should never count as the nearest
+pub(crate) fn cmp_nan_highest(a: &f64, b: &f64) -> Ordering {
+    a.is_nan().cmp(&b.is_nan()).then_with(|| a.total_cmp(b))
+}
+
+// Pairwise summation: halve the slice until the pieces are small enough to
+// add in sequence. The split points depend only on the length, so the
+// result is the same however the halves are scheduled
+pub(crate) fn pairwise_sum<T: Float>(vals: &[T]) -> T {
+    if vals.len() <= PAIRWISE_BLOCK {
+        return vals.iter().fold(T::zero(), |acc, &val| acc + val);
+    }
+
+    let mid = vals.len() / 2;
+    pairwise_sum(&vals[..mid]) + pairwise_sum(&vals[mid..])
+}
+
+// Same tree as `pairwise_sum`, with the top `depth` levels of halves run on
+// separate threads
+pub(crate) fn pairwise_sum_parallel<T: Float + Send + Sync>(vals: &[T], depth: usize) -> T {
+    if depth == 0 || vals.len() <= PAIRWISE_BLOCK {
+        return pairwise_sum(vals);
+    }
+
+    let (left, right) = vals.split_at(vals.len() / 2);
+    thread::scope(|scope| {
+        let left = scope.spawn(|| pairwise_sum_parallel(left, depth - 1));
+        let right = pairwise_sum_parallel(right, depth - 1);
+
+        left.join().unwrap() + right
+    })
+}
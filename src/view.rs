@@ -0,0 +1,114 @@
+use crate::matrix::Matrix;
+use std::ops::Range;
+
+// Read-only window onto another matrix's data. Element (i, j) of the view
+// lives at offset + i·row_stride + j·col_stride in the parent, so blocks
+// and transposes need no copy
+
+#[derive(Clone, Copy, Debug)]
+pub struct MatrixView<'a, T> {
+    data: &'a [T],
+    rows: usize,
+    cols: usize,
+    offset: usize,
+    row_stride: usize,
+    col_stride: usize,
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn view(&self) -> MatrixView<'_, T> {
+        MatrixView {
+            data: &self.data,
+            rows: self.rows,
+            cols: self.cols,
+            offset: 0,
+            row_stride: self.cols,
+            col_stride: 1,
+        }
+    }
+
+    // Zero-copy counterpart of `submatrix`
+    pub fn subview(&self, rows: Range<usize>, cols: Range<usize>) -> MatrixView<'_, T> {
+        self.view().subview(rows, cols)
+    }
+}
+
+impl<'a, T: Clone> MatrixView<'a, T> {
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn at_ref(&self, i: usize, j: usize) -> &'a T {
+        assert!(i < self.rows);
+        assert!(j < self.cols);
+
+        &self.data[self.offset + i * self.row_stride + j * self.col_stride]
+    }
+
+    pub fn at(&self, i: usize, j: usize) -> T {
+        self.at_ref(i, j).clone()
+    }
+
+    pub fn subview(&self, rows: Range<usize>, cols: Range<usize>) -> Self {
+        assert!(rows.start <= rows.end && rows.end <= self.rows);
+        assert!(cols.start <= cols.end && cols.end <= self.cols);
+
+        Self {
+            offset: self.offset + rows.start * self.row_stride + cols.start * self.col_stride,
+            rows: rows.len(),
+            cols: cols.len(),
+            ..*self
+        }
+    }
+
+    // Swaps the strides, so the transpose is also free
+    pub fn transpose(&self) -> Self {
+        Self {
+            rows: self.cols,
+            cols: self.rows,
+            row_stride: self.col_stride,
+            col_stride: self.row_stride,
+            ..*self
+        }
+    }
+
+    // Elements in row-major order of the view
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> + '_ {
+        (0..self.rows).flat_map(move |i| (0..self.cols).map(move |j| self.at_ref(i, j)))
+    }
+
+    pub fn to_matrix(&self) -> Matrix<T> {
+        Matrix::from(self.rows, self.cols, self.iter().cloned().collect())
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn view_test() {
+        let mat = Matrix::from(3, 4, (0..12).collect());
+
+        let block = mat.subview(1..3, 2..4);
+        assert_eq!(block.shape(), (2, 2));
+        assert_eq!(block.at(1, 0), mat.at(2, 2));
+        assert_eq!(block.to_matrix(), mat.submatrix(1..3, 2..4));
+        assert_eq!(
+            block.iter().cloned().collect::<Vec<_>>(),
+            vec![6, 7, 10, 11]
+        );
+
+        // Views of views and transposed views still read the parent
+        let nested = mat.view().subview(1..3, 1..4).subview(0..2, 1..3);
+        assert_eq!(nested.to_matrix(), block.to_matrix());
+        assert_eq!(
+            block.transpose().to_matrix(),
+            mat.submatrix(1..3, 2..4).transpose()
+        );
+
+        assert!(std::ptr::eq(block.at_ref(0, 0), &mat.data[6]));
+    }
+}